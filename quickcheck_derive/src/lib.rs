@@ -0,0 +1,381 @@
+//! This crate provides `#[derive(Arbitrary)]`. Its use is documented in
+//! the `quickcheck` crate.
+
+#![crate_name = "quickcheck_derive"]
+#![doc(html_root_url = "http://burntsushi.net/rustdoc/quickcheck")]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, TokenStream as Tokens, TokenTree};
+use quote::ToTokens;
+use syn::{
+    Attribute, Data, DeriveInput, Error, Field, Fields, Ident, LitStr,
+    Member, Result, Variant,
+};
+use syn::spanned::Spanned;
+
+/// Derives `quickcheck::Arbitrary` and `quickcheck::Shrink` for a struct
+/// or an enum.
+///
+/// For a struct, `arbitrary` generates every field and `shrink` shrinks the
+/// fields one at a time, like the impls for tuples. For an enum,
+/// `arbitrary` picks a variant with `quickcheck::choose_variant` and then
+/// generates its fields, while `shrink` first yields every earlier variant
+/// without fields and then shrinks the fields of the current variant.
+///
+/// The fields of a variant that refers to the enum itself are generated
+/// with `Gen::recurse`. When it returns `None`, the first variant that
+/// doesn't refer to the enum is generated instead, with its fields at size
+/// zero. This keeps recursive enums from growing without bound, and an
+/// enum whose every variant refers to itself is rejected. Only the name of
+/// the enum and `Self` are looked for in the types of the fields, so enums
+/// that refer to each other aren't bounded this way; they need a
+/// hand-written `Arbitrary` impl that uses `Gen::recurse`.
+///
+/// Each variant is weighted by `quickcheck::variant_weight` unless it has
+/// a `#[quickcheck(weight = "N")]` attribute.
+///
+/// A struct field with an `#[arbitrary(fixed)]` attribute is never
+/// shrunk, and is always generated as the same field of the base value of
+/// the struct, which is set with `StdGen::with_fixture` or
+/// `QuickCheck::fixture`. See `quickcheck::Fixed`.
+#[proc_macro_derive(Arbitrary, attributes(quickcheck, arbitrary))]
+pub fn derive_arbitrary(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match expand_derive_arbitrary(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_error(err).into(),
+    }
+}
+
+/// Reports `err` with `compile_error!`.
+///
+/// `Error::to_compile_error` can't be used because it expands to
+/// `::core::compile_error!`, which doesn't resolve in crates that use the
+/// 2015 edition.
+fn compile_error(err: Error) -> Tokens {
+    err.into_iter().map(|e| {
+        let msg = e.to_string();
+        quote_spanned!(e.span()=> compile_error!(#msg);)
+    }).collect()
+}
+
+fn expand_derive_arbitrary(mut input: DeriveInput) -> Result<Tokens> {
+    let name = input.ident.clone();
+    let (arbitrary, shrink) = match input.data {
+        Data::Struct(ref data) => {
+            let ctor = quote!(#name);
+            let mut fixed = vec![];
+            for f in &data.fields {
+                fixed.push(is_fixed(f)?);
+            }
+            (arbitrary_struct(&ctor, &data.fields, &fixed),
+             shrink_struct(&ctor, &data.fields, &fixed))
+        }
+        Data::Enum(ref data) if data.variants.is_empty() => {
+            return Err(Error::new(
+                name.span(),
+                format!("cannot derive `Arbitrary` for `{}` because it has \
+                         no variants", name)));
+        }
+        Data::Enum(ref data) => {
+            for f in data.variants.iter().flat_map(|v| v.fields.iter()) {
+                if is_fixed(f)? {
+                    return Err(Error::new(
+                        f.span(),
+                        "`#[arbitrary(fixed)]` is only supported on the \
+                         fields of a struct"));
+                }
+            }
+            let variants: Vec<&Variant> = data.variants.iter().collect();
+            (arbitrary_enum(&name, &variants)?,
+             shrink_enum(&name, &variants))
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                name.span(), "cannot derive `Arbitrary` for a union"));
+        }
+    };
+
+    let params: Vec<Ident> =
+        input.generics.type_params().map(|p| p.ident.clone()).collect();
+    {
+        let clause = input.generics.make_where_clause();
+        for param in &params {
+            clause.predicates.push(
+                syn::parse_quote!(#param: ::quickcheck::Arbitrary));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::quickcheck::Arbitrary
+                for #name #ty_generics #where_clause {
+            fn arbitrary<G: ::quickcheck::Gen>(g: &mut G) -> Self {
+                #arbitrary
+            }
+        }
+
+        impl #impl_generics ::quickcheck::Shrink
+                for #name #ty_generics #where_clause {
+            fn shrink(&self) -> ::quickcheck::Shrinker<Self> {
+                #shrink
+            }
+        }
+    })
+}
+
+/// Builds `ctor` with `Arbitrary::arbitrary(g)` for every field.
+fn arbitrary_fields(ctor: &Tokens, fields: &Fields) -> Tokens {
+    let values = fields.iter().enumerate().map(|(i, f)| {
+        let member = member(i, f);
+        quote!(#member: ::quickcheck::Arbitrary::arbitrary(g))
+    });
+    quote!(#ctor { #(#values,)* })
+}
+
+/// Like `arbitrary_fields`, but takes the fields marked in `fixed` from
+/// the base value of the struct.
+fn arbitrary_struct(ctor: &Tokens, fields: &Fields, fixed: &[bool])
+                   -> Tokens {
+    if !fixed.contains(&true) {
+        return arbitrary_fields(ctor, fields);
+    }
+    let values = fields.iter().enumerate().map(|(i, f)| {
+        let member = member(i, f);
+        if fixed[i] {
+            quote!(#member: __qc_base.#member)
+        } else {
+            quote!(#member: ::quickcheck::Arbitrary::arbitrary(g))
+        }
+    });
+    quote! {
+        let __qc_base: Self = ::quickcheck::base_value(g);
+        #ctor { #(#values,)* }
+    }
+}
+
+fn arbitrary_enum(name: &Ident, variants: &[&Variant]) -> Result<Tokens> {
+    let mut weights = vec![];
+    for v in variants {
+        weights.push(match variant_weight(&v.attrs)? {
+            Some(w) => Literal::f64_suffixed(w).into_token_stream(),
+            None => {
+                let n = v.fields.len();
+                quote!(::quickcheck::variant_weight(#n))
+            }
+        });
+    }
+    // The first variant that doesn't refer to the enum is what a recursive
+    // variant turns into when `Gen::recurse` runs out. Its fields, if any,
+    // are generated at size zero.
+    let recursive: Vec<bool> =
+        variants.iter().map(|v| is_recursive(name, v)).collect();
+    let base = match recursive.iter().position(|&r| !r) {
+        Some(i) => {
+            let ident = &variants[i].ident;
+            let base = arbitrary_fields(&quote!(#name::#ident),
+                                        &variants[i].fields);
+            if variants[i].fields.is_empty() {
+                base
+            } else {
+                quote!(::quickcheck::Gen::with_size(g, 0, |g| #base))
+            }
+        }
+        None => {
+            return Err(Error::new(
+                name.span(),
+                format!("cannot derive `Arbitrary` for `{}` because every \
+                         variant refers to it, so there is no value to \
+                         generate once `Gen::recurse` runs out", name)));
+        }
+    };
+    let arms = variants.iter().enumerate().map(|(i, v)| {
+        let ident = &v.ident;
+        let value = arbitrary_fields(&quote!(#name::#ident), &v.fields);
+        let value = if recursive[i] {
+            quote! {
+                match ::quickcheck::Gen::recurse(g, |g| #value) {
+                    ::std::option::Option::Some(value) => value,
+                    ::std::option::Option::None => #base,
+                }
+            }
+        } else {
+            value
+        };
+        quote!(#i => #value,)
+    });
+    Ok(quote! {
+        match ::quickcheck::choose_variant(g, &[#(#weights),*]) {
+            #(#arms)*
+            _ => unreachable!(),
+        }
+    })
+}
+
+/// Returns the weight given by `#[quickcheck(weight = "N")]`, if any.
+fn variant_weight(attrs: &[Attribute]) -> Result<Option<f64>> {
+    let mut weight = None;
+    for attr in attrs {
+        if !attr.path().is_ident("quickcheck") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let expected = "expected `weight = \"N\"` with a non-negative \
+                            number `N`";
+            if !meta.path.is_ident("weight") {
+                return Err(meta.error(expected));
+            }
+            let value: LitStr = meta.value()?.parse()?;
+            match value.value().parse::<f64>() {
+                Ok(w) if w >= 0.0 && w.is_finite() => {
+                    weight = Some(w);
+                    Ok(())
+                }
+                _ => Err(Error::new(value.span(), expected)),
+            }
+        })?;
+    }
+    Ok(weight)
+}
+
+/// Returns whether `f` has an `#[arbitrary(fixed)]` attribute.
+fn is_fixed(f: &Field) -> Result<bool> {
+    let mut fixed = false;
+    for attr in &f.attrs {
+        if !attr.path().is_ident("arbitrary") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("fixed") {
+                return Err(meta.error("expected `fixed`"));
+            }
+            fixed = true;
+            Ok(())
+        })?;
+    }
+    Ok(fixed)
+}
+
+/// Returns whether a field of `v` mentions `Self` or the enum `name`.
+///
+/// Only the tokens of the field types are looked at, so a variant that
+/// refers to `name` through another type isn't recursive.
+fn is_recursive(name: &Ident, v: &Variant) -> bool {
+    fn mentions(name: &Ident, tokens: Tokens) -> bool {
+        tokens.into_iter().any(|t| match t {
+            TokenTree::Ident(ref i) => i == name || i == "Self",
+            TokenTree::Group(ref g) => mentions(name, g.stream()),
+            _ => false,
+        })
+    }
+    v.fields.iter().any(|f| mentions(name, f.ty.to_token_stream()))
+}
+
+fn shrink_struct(ctor: &Tokens, fields: &Fields, fixed: &[bool]) -> Tokens {
+    let pat = field_pattern(ctor, fields);
+    let shrunk = shrink_fields(ctor, fields, fixed);
+    quote! {
+        let #pat = *self;
+        #shrunk
+    }
+}
+
+fn shrink_enum(name: &Ident, variants: &[&Variant]) -> Tokens {
+    let arms = variants.iter().enumerate().map(|(i, v)| {
+        let ident = &v.ident;
+        let ctor = quote!(#name::#ident);
+        let pat = field_pattern(&ctor, &v.fields);
+        let simpler = variants[..i].iter()
+                                   .filter(|v| v.fields.is_empty())
+                                   .map(|v| {
+                                       let ident = &v.ident;
+                                       quote!(#name::#ident {})
+                                   });
+        let fixed = vec![false; v.fields.len()];
+        let shrunk = shrink_fields(&ctor, &v.fields, &fixed);
+        quote! {
+            #pat => {
+                let simpler: ::std::vec::Vec<Self> = vec![#(#simpler),*];
+                ::std::boxed::Box::new(
+                    simpler.into_iter().chain(#shrunk))
+            }
+        }
+    });
+    quote! {
+        match *self {
+            #(#arms)*
+        }
+    }
+}
+
+/// Binds every field of `ctor` by reference to `__qc_field_N`.
+fn field_pattern(ctor: &Tokens, fields: &Fields) -> Tokens {
+    let binds = fields.iter().enumerate().map(|(i, f)| {
+        let member = member(i, f);
+        let var = field_var(i);
+        quote!(#member: ref #var)
+    });
+    quote!(#ctor { #(#binds,)* })
+}
+
+/// Shrinks the fields bound by `field_pattern` as the nested tuple
+/// `(a, (b, (c, ())))` and builds `ctor` from every shrunk tuple.
+///
+/// The fields marked in `fixed` are left out of the tuple and copied into
+/// every shrunk value instead.
+fn shrink_fields(ctor: &Tokens, fields: &Fields, fixed: &[bool]) -> Tokens {
+    let vars: Vec<Ident> = (0..fields.len()).map(field_var).collect();
+    let shrunk: Vec<&Ident> =
+        vars.iter().zip(fixed).filter(|v| !*v.1).map(|v| v.0).collect();
+    let value = shrunk.iter().rev().fold(quote!(()), |rest, var| {
+        quote!((::std::clone::Clone::clone(#var), #rest))
+    });
+    let pat = shrunk.iter().rev().fold(quote!(()), |rest, var| {
+        quote!((#var, #rest))
+    });
+    let kept = vars.iter().zip(fixed).filter(|v| *v.1).map(|v| {
+        let (var, kept) = (v.0, kept_var(v.0));
+        quote!(let #kept = ::std::clone::Clone::clone(#var);)
+    });
+    let values = fields.iter().enumerate().map(|(i, f)| {
+        let member = member(i, f);
+        let var = &vars[i];
+        if fixed[i] {
+            let kept = kept_var(var);
+            quote!(#member: ::std::clone::Clone::clone(&#kept))
+        } else {
+            quote!(#member: #var)
+        }
+    });
+    quote! {{
+        #(#kept)*
+        ::quickcheck::map_shrinker(
+            ::quickcheck::Shrink::shrink(&#value),
+            move |#pat| #ctor { #(#values,)* })
+    }}
+}
+
+fn member(i: usize, f: &syn::Field) -> Member {
+    match f.ident {
+        Some(ref ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(syn::Index {
+            index: i as u32,
+            span: f.span(),
+        }),
+    }
+}
+
+fn field_var(i: usize) -> Ident {
+    Ident::new(&format!("__qc_field_{}", i), proc_macro2::Span::call_site())
+}
+
+/// The variable that `shrink_fields` copies the fixed field `var` to.
+fn kept_var(var: &Ident) -> Ident {
+    Ident::new(&format!("{}_kept", var), var.span())
+}
@@ -0,0 +1,224 @@
+extern crate quickcheck;
+#[macro_use]
+extern crate quickcheck_derive;
+extern crate trybuild;
+
+use std::cmp::max;
+
+use quickcheck::{Arbitrary, Shrink, StdGen, quickcheck};
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+struct Meters(u32);
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+struct Pair<T> {
+    first: T,
+    second: T,
+}
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+struct Config {
+    #[arbitrary(fixed)]
+    name: String,
+    retries: u32,
+    #[arbitrary(fixed)]
+    verbose: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+enum Shape {
+    Empty,
+    Circle(u32),
+    Rect { w: u32, h: u32 },
+}
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+enum Arity {
+    Zero,
+    One(u8),
+    Two(u8, u8),
+    Three(u8, u8, u8),
+}
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+enum Weighted {
+    Rare,
+    #[quickcheck(weight = "1000")]
+    Common,
+}
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+enum Expr {
+    Lit,
+    Neg(Box<Expr>),
+    Add(Box<Self>, Box<Self>),
+}
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+enum Term {
+    Lit(i64),
+    Add(Box<Term>, Box<Term>),
+}
+
+#[derive(Clone, Debug, PartialEq, Arbitrary)]
+enum Bytes {
+    Nothing,
+    Many(Vec<u8>),
+}
+
+impl Expr {
+    fn depth(&self) -> usize {
+        match *self {
+            Expr::Lit => 0,
+            Expr::Neg(ref e) => 1 + e.depth(),
+            Expr::Add(ref a, ref b) => 1 + max(a.depth(), b.depth()),
+        }
+    }
+}
+
+impl Term {
+    fn depth(&self) -> usize {
+        match *self {
+            Term::Lit(_) => 0,
+            Term::Add(ref a, ref b) => 1 + max(a.depth(), b.depth()),
+        }
+    }
+}
+
+fn generate<A: Arbitrary>(n: usize) -> Vec<A> {
+    let mut g = StdGen::from_seed(1, 10);
+    (0..n).map(|_| A::arbitrary(&mut g)).collect()
+}
+
+#[test]
+fn structs() {
+    let points: Vec<Point> = generate(100);
+    assert!(points.iter().any(|p| p.x != p.y));
+    assert_eq!(Point { x: 2, y: 0 }.shrink().collect::<Vec<_>>(),
+               vec![Point { x: 0, y: 0 }, Point { x: 1, y: 0 }]);
+    assert_eq!(Meters(3).shrink().collect::<Vec<_>>(),
+               vec![Meters(0), Meters(2)]);
+    assert_eq!(Pair { first: 0u8, second: 1 }.shrink().collect::<Vec<_>>(),
+               vec![Pair { first: 0, second: 0 }]);
+}
+
+#[test]
+fn fixed_fields() {
+    let base = Config { name: "base".to_string(), retries: 3, verbose: true };
+    let mut g = StdGen::from_seed(1, 10).with_fixture(base.clone());
+    let configs: Vec<Config> =
+        (0..100).map(|_| Arbitrary::arbitrary(&mut g)).collect();
+    for c in &configs {
+        assert_eq!(c, &Config { retries: c.retries, ..base.clone() });
+    }
+    assert!(configs.iter().any(|c| c.retries != configs[0].retries));
+    assert_eq!(Config { retries: 2, ..base.clone() }.shrink()
+                                                      .collect::<Vec<_>>(),
+               vec![Config { retries: 0, ..base.clone() },
+                    Config { retries: 1, ..base.clone() }]);
+}
+
+#[test]
+fn unit_enums() {
+    let colors: Vec<Color> = generate(100);
+    for c in &[Color::Red, Color::Green, Color::Blue] {
+        assert!(colors.contains(c));
+    }
+    assert_eq!(Color::Red.shrink().count(), 0);
+    assert_eq!(Color::Blue.shrink().collect::<Vec<_>>(),
+               vec![Color::Red, Color::Green]);
+}
+
+#[test]
+fn data_enums() {
+    let shapes: Vec<Shape> = generate(100);
+    assert!(shapes.contains(&Shape::Empty));
+    assert!(shapes.iter().any(|s| matches!(*s, Shape::Circle(_))));
+    assert!(shapes.iter().any(|s| matches!(*s, Shape::Rect { .. })));
+    assert_eq!(Shape::Circle(2).shrink().collect::<Vec<_>>(),
+               vec![Shape::Empty, Shape::Circle(0), Shape::Circle(1)]);
+    assert_eq!(Shape::Rect { w: 1, h: 0 }.shrink().collect::<Vec<_>>(),
+               vec![Shape::Empty, Shape::Rect { w: 0, h: 0 }]);
+}
+
+#[test]
+fn default_weights() {
+    let mut counts = [0; 4];
+    for a in generate::<Arity>(10000) {
+        counts[match a {
+            Arity::Zero => 0,
+            Arity::One(_) => 1,
+            Arity::Two(_, _) => 2,
+            Arity::Three(_, _, _) => 3,
+        }] += 1;
+    }
+    // Expected: 1/2.08, 1/4.17, 1/6.25 and 1/8.33 of the draws.
+    assert!(counts.windows(2).all(|w| w[0] > w[1]), "{:?}", counts);
+    assert!(counts[0] > 4000 && counts[3] > 800, "{:?}", counts);
+}
+
+#[test]
+fn explicit_weights() {
+    let ws: Vec<Weighted> = generate(100);
+    let rare = ws.iter().filter(|w| **w == Weighted::Rare).count();
+    assert!(rare < 10);
+}
+
+#[test]
+fn recursive_enums() {
+    let mut g = StdGen::from_seed(1, 1000);
+    for _ in 0..100 {
+        let e: Expr = Arbitrary::arbitrary(&mut g);
+        // The size of 1000 can only be halved ten times.
+        assert!(e.depth() <= 10);
+    }
+}
+
+#[test]
+fn recursive_enums_with_data_base() {
+    // `Lit` has a field, but it is still what `Add` turns into once the
+    // size runs out.
+    let mut g = StdGen::from_seed(1, 1000);
+    let terms: Vec<Term> =
+        (0..100).map(|_| Arbitrary::arbitrary(&mut g)).collect();
+    assert!(terms.iter().all(|t| t.depth() <= 10));
+    assert!(terms.iter().any(|t| t.depth() > 1));
+}
+
+#[test]
+fn non_recursive_enums() {
+    // `Many` doesn't refer to `Bytes`, so its vector is generated at the
+    // full size instead of half of it.
+    let mut g = StdGen::from_seed(1, 100);
+    let longest = (0..100).map(|_| match Arbitrary::arbitrary(&mut g) {
+        Bytes::Nothing => 0,
+        Bytes::Many(xs) => xs.len(),
+    }).max().unwrap();
+    assert!(longest >= 50, "{}", longest);
+}
+
+#[test]
+fn compile_errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
+
+#[test]
+fn properties() {
+    fn prop(shapes: Vec<Shape>) -> bool {
+        shapes.iter().all(|s| s.clone() == *s)
+    }
+    quickcheck(prop as fn(Vec<Shape>) -> bool);
+}
@@ -0,0 +1,12 @@
+extern crate quickcheck;
+#[macro_use]
+extern crate quickcheck_derive;
+
+#[derive(Clone, Debug, Arbitrary)]
+enum Coin {
+    Heads,
+    #[quickcheck(weight = "-1")]
+    Tails,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+extern crate quickcheck;
+#[macro_use]
+extern crate quickcheck_derive;
+
+#[derive(Clone, Debug, Arbitrary)]
+enum Void {}
+
+fn main() {}
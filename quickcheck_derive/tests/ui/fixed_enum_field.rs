@@ -0,0 +1,11 @@
+extern crate quickcheck;
+#[macro_use]
+extern crate quickcheck_derive;
+
+#[derive(Clone, Debug, Arbitrary)]
+enum Message {
+    Quit,
+    Write(#[arbitrary(fixed)] String),
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+extern crate quickcheck;
+#[macro_use]
+extern crate quickcheck_derive;
+
+#[derive(Clone, Debug, Arbitrary)]
+enum Stream {
+    Cons(u8, Box<Stream>),
+}
+
+fn main() {}
@@ -1,19 +1,272 @@
-use std::collections::hash_map::HashMap;
-use std::hash::Hash;
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::borrow::Cow;
+use std::char;
+use std::cmp::{self, Ordering, Reverse};
+use std::collections::{
+    BTreeMap, BTreeSet, BinaryHeap, Bound, HashMap, HashSet, LinkedList,
+    VecDeque,
+};
+use std::f32;
+use std::ffi::{CString, OsString};
+use std::f64;
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
 use std::mem;
+use std::net::{
+    IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6,
+};
+use std::num::{
+    NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU16,
+    NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize, Wrapping,
+};
+use std::ops::{Add, Deref, Range, RangeInclusive, Sub};
+use std::path::{self, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::usize;
 
-use rand::Rng;
+use rand::{Rand, Rng};
+use rand::distributions::range::SampleRange;
 
+#[cfg(feature = "bytes")]
+use bytes::{Bytes, BytesMut};
 #[cfg(feature = "collect_impls")]
 use collect::TrieMap;
+#[cfg(feature = "rust_decimal")]
+use rust_decimal::Decimal;
+#[cfg(feature = "semver")]
+use semver::{Identifier, Version};
 
-/// `Gen` wraps a `rand::Rng` with parameters to control the distribution of
-/// random values.
+use shrink::Shrink;
+
+/// `Gen` is a source of random values with parameters to control their
+/// distribution.
 ///
-/// A value with type satisfying the `Gen` trait can be constructed with the
-/// `gen` function in this crate.
-pub trait Gen : Rng {
+/// Only `next_u32` and `size` must be implemented. Everything else,
+/// including the few `rand::Rng` methods that `Arbitrary` impls need, such
+/// as `gen_range` and `choose`, is built on top of them. This means that a
+/// generator doesn't have to implement `rand::Rng`, so a deterministic
+/// generator for replaying a test is easy to write. `StdGen` implements
+/// `Gen` for any `rand::Rng`.
+pub trait Gen {
+    /// Returns a uniformly random `u32`.
+    fn next_u32(&mut self) -> u32;
+
+    /// Returns a uniformly random `u64`.
+    ///
+    /// By default, this is built from two calls to `next_u32`.
+    fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) | self.next_u32() as u64
+    }
+
+    /// Fills `dest` with random bytes.
+    ///
+    /// By default, this is built from calls to `next_u64`.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let mut x = self.next_u64();
+            for b in chunk {
+                *b = x as u8;
+                x >>= 8;
+            }
+        }
+    }
+
     fn size(&self) -> usize;
+
+    /// Sets the value returned by `size`.
+    ///
+    /// Generators with a fixed size ignore it.
+    fn set_size(&mut self, _size: usize) {}
+
+    /// Returns the length that generated collections must have at least.
+    ///
+    /// Collections are generated with a length in `[min_size, size)`, or
+    /// exactly `min_size` if the size isn't bigger than it. Maps and sets
+    /// can still be shorter if their elements (or keys) have fewer than
+    /// `min_size` distinct values, e.g., a `HashSet<bool>`.
+    ///
+    /// By default, the minimum size is zero.
+    fn min_size(&self) -> usize { 0 }
+
+    /// Sets the value returned by `min_size`.
+    ///
+    /// Generators with a fixed minimum size ignore it.
+    fn set_min_size(&mut self, _min_size: usize) {}
+
+    /// Calls `f` with the size set to `size`, and restores the current size
+    /// afterwards.
+    fn with_size<T, F>(&mut self, size: usize, f: F) -> T
+            where Self: Sized, F: FnOnce(&mut Self) -> T {
+        let old = self.size();
+        self.set_size(size);
+        let x = f(self);
+        self.set_size(old);
+        x
+    }
+
+    /// Returns how many more levels recursive generators may descend.
+    ///
+    /// Unlike `size`, which controls how big values are, this is an
+    /// absolute limit on how deeply nested they are. A recursive generator
+    /// should produce a base case when the depth is zero and generate its
+    /// children with `with_depth(depth - 1, ...)` otherwise.
+    ///
+    /// By default, the depth is unlimited (`usize::MAX`).
+    fn depth(&self) -> usize { usize::MAX }
+
+    /// Sets the value returned by `depth`.
+    ///
+    /// Generators that don't keep track of the depth ignore it.
+    fn set_depth(&mut self, _depth: usize) {}
+
+    /// Calls `f` with the depth set to `depth`, and restores the current
+    /// depth afterwards.
+    fn with_depth<T, F>(&mut self, depth: usize, f: F) -> T
+            where Self: Sized, F: FnOnce(&mut Self) -> T {
+        let old = self.depth();
+        self.set_depth(depth);
+        let x = f(self);
+        self.set_depth(old);
+        x
+    }
+
+    /// Calls `f` to generate the children of a recursive value, with the
+    /// size halved and the depth decreased by one, and restores both
+    /// afterwards.
+    ///
+    /// Returns `None` without calling `f` if the size or the depth is
+    /// already zero, in which case the caller should generate a value
+    /// without children, e.g.,
+    /// `g.recurse(|g| Tree::Node(..)).unwrap_or(Tree::Leaf)`. Since the
+    /// size halves at every level, this bounds how deep values get even
+    /// when every level recurses. (This relies on `set_size` or
+    /// `set_depth` being implemented.)
+    fn recurse<T, F>(&mut self, f: F) -> Option<T>
+            where Self: Sized, F: FnOnce(&mut Self) -> T {
+        let (size, depth) = (self.size(), self.depth());
+        if size == 0 || depth == 0 {
+            return None;
+        }
+        self.set_depth(depth - 1);
+        let x = self.with_size(size / 2, f);
+        self.set_depth(depth);
+        Some(x)
+    }
+
+    /// Returns whether integers should sometimes be corner values.
+    ///
+    /// When this is true, the `Arbitrary` impls for integers return one of
+    /// `0`, `1`, the size, `MAX` and, for signed integers, `-1`, minus the
+    /// size and `MIN` one time in ten, instead of a value less than the
+    /// size. This finds overflow and off-by-one bugs much more often, but
+    /// properties must then be correct for the extreme values of every
+    /// integer they take.
+    ///
+    /// By default, corner values are not generated.
+    fn corner_values(&self) -> bool { false }
+
+    /// Sets the value returned by `corner_values`.
+    ///
+    /// Generators that never generate corner values ignore it.
+    fn set_corner_values(&mut self, _yes: bool) {}
+
+    /// Returns the base values that `Fixed` is generated as, if this
+    /// generator has any.
+    ///
+    /// By default, there are none.
+    fn fixtures(&self) -> Option<&Fixtures> { None }
+
+    /// Sets the value returned by `fixtures`.
+    ///
+    /// Generators without base values ignore it.
+    fn set_fixtures(&mut self, _fixtures: Fixtures) {}
+
+    /// Returns the base value of type `T`, if this generator has one.
+    fn fixture<T: Any>(&self) -> Option<&T> where Self: Sized {
+        self.fixtures().and_then(|fs| fs.get())
+    }
+
+    /// Picks one of `items`, each with a probability proportional to its
+    /// weight. Items with a weight of zero are never picked.
+    ///
+    /// This is `choose_variant` with integer weights.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `items` is empty or all of its weights are zero.
+    fn choose_weighted<'a, T>(&mut self, items: &'a [(u32, T)]) -> &'a T
+            where Self: Sized {
+        assert!(!items.is_empty(), "cannot choose from an empty slice");
+        assert!(items.iter().any(|&(w, _)| w > 0),
+                "cannot choose when all weights are zero");
+        let weights: Vec<f64> = items.iter().map(|&(w, _)| w as f64)
+                                     .collect();
+        &items[choose_variant(self, &weights)].1
+    }
+
+    /// Returns a random value of type `T`, like `rand::Rng::gen`.
+    fn gen<T: Rand>(&mut self) -> T where Self: Sized {
+        Rand::rand(&mut GenRng(self))
+    }
+
+    /// Returns a random value in `[low, high)`, like
+    /// `rand::Rng::gen_range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low >= high`.
+    fn gen_range<T>(&mut self, low: T, high: T) -> T
+            where Self: Sized, T: PartialOrd + SampleRange {
+        GenRng(self).gen_range(low, high)
+    }
+
+    /// Returns true with a probability of `1 / n`, or always if `n <= 1`.
+    fn gen_weighted_bool(&mut self, n: u32) -> bool where Self: Sized {
+        n <= 1 || self.gen_range(0, n) == 0
+    }
+
+    /// Returns true with a probability of `p`, after clamping `p` to
+    /// `[0, 1]`.
+    ///
+    /// No random number is drawn when `p` is `0` or `1`.
+    fn gen_bool(&mut self, p: f64) -> bool where Self: Sized {
+        if p <= 0.0 {
+            false
+        } else if p >= 1.0 {
+            true
+        } else {
+            self.gen::<f64>() < p
+        }
+    }
+
+    /// Returns `len` random bytes, generated with a single call to
+    /// `fill_bytes`.
+    fn gen_bytes(&mut self, len: usize) -> Vec<u8> {
+        let mut bytes = vec![0; len];
+        self.fill_bytes(&mut bytes);
+        bytes
+    }
+
+    /// Picks one of `values` uniformly, or returns `None` if it is empty.
+    fn choose<'a, T>(&mut self, values: &'a [T]) -> Option<&'a T>
+            where Self: Sized {
+        if values.is_empty() {
+            None
+        } else {
+            Some(&values[self.gen_range(0, values.len())])
+        }
+    }
+}
+
+/// Lets the distributions in `rand` draw from a `Gen`.
+struct GenRng<'a, G: 'a>(&'a mut G);
+
+impl<'a, G: Gen> Rng for GenRng<'a, G> {
+    fn next_u32(&mut self) -> u32 { self.0.next_u32() }
+    fn next_u64(&mut self) -> u64 { self.0.next_u64() }
+    fn fill_bytes(&mut self, dest: &mut [u8]) { self.0.fill_bytes(dest) }
 }
 
 /// StdGen is the default implementation of `Gen`.
@@ -23,6 +276,10 @@ pub trait Gen : Rng {
 pub struct StdGen<R> {
     rng: R,
     size: usize,
+    min_size: usize,
+    depth: usize,
+    corner_values: bool,
+    fixtures: Fixtures,
 }
 
 /// Returns a `StdGen` with the given configuration using any random number
@@ -33,23 +290,132 @@ pub struct StdGen<R> {
 /// and also will specify the maximum magnitude of a randomly generated number.
 impl<R: Rng> StdGen<R> {
     pub fn new(rng: R, size: usize) -> StdGen<R> {
-        StdGen { rng: rng, size: size }
+        StdGen {
+            rng: rng,
+            size: size,
+            min_size: 0,
+            depth: usize::MAX,
+            corner_values: false,
+            fixtures: Fixtures::new(),
+        }
+    }
+
+    /// Makes generated collections at least `min_size` long.
+    ///
+    /// See `Gen::min_size`.
+    pub fn with_min_size(mut self, min_size: usize) -> StdGen<R> {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Makes `value` the base value of type `T`, which `Fixed<T>` is
+    /// generated as.
+    ///
+    /// See `Fixed`.
+    pub fn with_fixture<T>(mut self, value: T) -> StdGen<R>
+            where T: Any + Send + Sync {
+        self.fixtures.insert(value);
+        self
+    }
+}
+
+impl StdGen<SplitMix64> {
+    /// Returns a `StdGen` whose random values are completely determined by
+    /// `seed`, so that two generators with the same seed and size generate
+    /// the same values.
+    ///
+    /// The random numbers come from `SplitMix64`, which is part of this
+    /// crate, so they stay the same when the `rand` crate is upgraded.
+    /// (Generators created with `new` get no such guarantee.) Note that
+    /// values built from them with `rand`, e.g., with `gen_range`, may
+    /// still change if `rand` changes how it does that.
+    ///
+    /// To make a failure reproducible, print `seed()` when a test fails
+    /// and pass the printed seed and the same size to `from_seed` to get a
+    /// generator that generates the same values again. `QuickCheck`
+    /// generates every test this way and prints the seed of a failing
+    /// test, which `QuickCheck::always_seeds` replays.
+    ///
+    /// ```rust
+    /// use quickcheck::{Arbitrary, StdGen};
+    ///
+    /// let mut g = StdGen::from_seed(42, 100);
+    /// let seed = g.seed();
+    /// let xs: Vec<i32> = Arbitrary::arbitrary(&mut g);
+    ///
+    /// let mut g = StdGen::from_seed(seed, 100);
+    /// assert_eq!(xs, Vec::<i32>::arbitrary(&mut g));
+    /// ```
+    pub fn from_seed(seed: u64, size: usize) -> StdGen<SplitMix64> {
+        StdGen::new(SplitMix64::new(seed), size)
+    }
+
+    /// Returns the seed that this generator was created with.
+    pub fn seed(&self) -> u64 { self.rng.seed() }
+}
+
+/// The SplitMix64 random number generator.
+///
+/// Its output for a given seed is fixed and will never change, which makes
+/// it suitable for seeds that are saved to reproduce failures. It is not
+/// suitable for cryptography.
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    seed: u64,
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// Returns a generator whose output is completely determined by `seed`.
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { seed: seed, state: seed }
+    }
+
+    /// Returns the seed that this generator was created with.
+    pub fn seed(&self) -> u64 { self.seed }
+}
+
+impl Rng for SplitMix64 {
+    fn next_u32(&mut self) -> u32 { (self.next_u64() >> 32) as u32 }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
     }
 }
 
-impl<R: Rng> Rng for StdGen<R> {
+impl<R: Rng> Gen for StdGen<R> {
     fn next_u32(&mut self) -> u32 { self.rng.next_u32() }
 
     // some RNGs implement these more efficiently than the default, so
     // we might as well defer to them.
     fn next_u64(&mut self) -> u64 { self.rng.next_u64() }
     fn fill_bytes(&mut self, dest: &mut [u8]) { self.rng.fill_bytes(dest) }
-}
 
-impl<R: Rng> Gen for StdGen<R> {
     fn size(&self) -> usize { self.size }
+    fn set_size(&mut self, size: usize) { self.size = size; }
+    fn min_size(&self) -> usize { self.min_size }
+    fn set_min_size(&mut self, min_size: usize) { self.min_size = min_size; }
+    fn depth(&self) -> usize { self.depth }
+    fn set_depth(&mut self, depth: usize) { self.depth = depth; }
+    fn corner_values(&self) -> bool { self.corner_values }
+    fn set_corner_values(&mut self, yes: bool) { self.corner_values = yes; }
+    fn fixtures(&self) -> Option<&Fixtures> { Some(&self.fixtures) }
+    fn set_fixtures(&mut self, fixtures: Fixtures) {
+        self.fixtures = fixtures;
+    }
 }
 
+/// The type of the iterator returned by `Shrink::shrink`.
+///
+/// This is used by `#[derive(Arbitrary)]` in `quickcheck_derive`, so that
+/// the code it generates doesn't depend on how trait objects are spelled.
+#[doc(hidden)]
+pub type Shrinker<A> = Box<Iterator<Item=A>+'static>;
+
 struct EmptyShrinker<A> {
     _phantom: ::std::marker::PhantomData<A>,
 }
@@ -78,9 +444,101 @@ pub fn single_shrinker<A: 'static>(value: A) -> Box<Iterator<Item=A>+'static> {
     Box::new(SingleShrinker { value: Some(value) })
 }
 
+/// Creates a shrinker that yields `f(x)` for each `x` yielded by `xs`.
+///
+/// This is useful for shrinking a wrapper type by shrinking the value it
+/// wraps, e.g., `map_shrinker(self.0.shrink(), Meters)`.
+pub fn map_shrinker<A, B, F>(xs: Box<Iterator<Item=A>+'static>, f: F)
+                            -> Box<Iterator<Item=B>+'static>
+        where A: 'static, B: 'static, F: FnMut(A) -> B + 'static {
+    Box::new(xs.map(f))
+}
+
+/// Creates a shrinker that yields only the values yielded by `xs` for
+/// which `keep` returns true.
+///
+/// This is useful for types whose values must satisfy an invariant that
+/// some shrunk values of their representation don't.
+pub fn filter_shrinker<A, F>(xs: Box<Iterator<Item=A>+'static>, keep: F)
+                            -> Box<Iterator<Item=A>+'static>
+        where A: 'static, F: FnMut(&A) -> bool + 'static {
+    Box::new(xs.filter(keep))
+}
+
+/// Generates a value with `f` at a different size than the one of `g`.
+///
+/// This is useful in generators of composite values, e.g., to generate a
+/// small key but a large value. It is the same as `g.with_size(size, f)`.
+pub fn resize<G, T, F>(g: &mut G, size: usize, f: F) -> T
+        where G: Gen, F: FnOnce(&mut G) -> T {
+    g.with_size(size, f)
+}
+
+/// Panics with a message explaining that no value of the type `name` can be
+/// generated because it has no values.
+///
+/// This is meant to implement `Arbitrary::arbitrary` by hand for
+/// uninhabited types, since `#[derive(Arbitrary)]` rejects an enum without
+/// variants at compile time:
+///
+/// ```rust
+/// use quickcheck::{Arbitrary, Gen, Shrink, empty_domain};
+///
+/// #[derive(Clone, Debug)]
+/// enum Void {}
+///
+/// impl Arbitrary for Void {
+///     fn arbitrary<G: Gen>(_: &mut G) -> Void { empty_domain("Void") }
+/// }
+///
+/// impl Shrink for Void {}
+/// ```
+pub fn empty_domain<T>(name: &str) -> T {
+    panic!("cannot generate a value of type `{}` because it has no values \
+            (e.g., it is an enum without variants), so properties that take \
+            one can never be tested", name)
+}
+
+/// Returns the default weight of an enum variant with `fields` fields.
+///
+/// Variants with fewer fields are more likely to be picked, so that
+/// generated values stay small: a variant without fields has weight `1.0`,
+/// one with a single field has weight `0.5` and so on. An explicit weight
+/// of `N` makes a variant `N` times as likely as one without fields.
+pub fn variant_weight(fields: usize) -> f64 {
+    1.0 / (fields + 1) as f64
+}
+
+/// Picks the index of an enum variant, with each index `i` chosen with a
+/// probability proportional to `weights[i]`.
+///
+/// # Panics
+///
+/// Panics if `weights` is empty or its weights don't sum to a positive
+/// number.
+pub fn choose_variant<G: Gen>(g: &mut G, weights: &[f64]) -> usize {
+    let total = weights.iter().fold(0.0, |a, &b| a + b);
+    assert!(total > 0.0, "cannot choose from variants with weights {:?}",
+            weights);
+    let mut x = g.gen_range(0.0, total);
+    for (i, &w) in weights.iter().enumerate() {
+        if x < w {
+            return i;
+        }
+        x -= w;
+    }
+    // Rounding errors can make `x` slightly larger than the last weight.
+    weights.iter().rposition(|&w| w > 0.0).unwrap()
+}
+
 /// `Arbitrary` describes types whose values can be randomly generated and
 /// shrunk.
 ///
+/// Values are shrunk with the supertrait `Shrink`, so a type that
+/// implements `Arbitrary` implements `Shrink` too. An empty
+/// `impl Shrink for T {}` is enough for a type whose values are never
+/// shrunk.
+///
 /// Aside from shrinking, `Arbitrary` is different from the `std::Rand` trait
 /// in that it uses a `Gen` to control the distribution of random values.
 ///
@@ -88,20 +546,49 @@ pub fn single_shrinker<A: 'static>(value: A) -> Box<Iterator<Item=A>+'static> {
 /// `Clone`. (I'm not sure if this is a permanent restriction.)
 ///
 /// They must also be sendable since every test is run inside its own task.
-/// (This permits failures to include task failures.)
-pub trait Arbitrary : Clone + Send + 'static {
+/// (This permits failures to include task failures.) In particular, this
+/// means that `Rc<A>` and `Rc<str>` can't implement `Arbitrary`. Use
+/// `Arc<A>`, which does, or `Box<str>` instead.
+///
+/// A type without any values, like an enum without variants, can't be
+/// generated, so a property that takes one can never be tested. If generic
+/// code needs such a type to implement `Arbitrary` anyway, its `arbitrary`
+/// should call `empty_domain`, which panics with a message that says so.
+pub trait Arbitrary : Shrink + Clone + Send + 'static {
     fn arbitrary<G: Gen>(g: &mut G) -> Self;
-    fn shrink(&self) -> Box<Iterator<Item=Self>+'static> {
-        empty_shrinker()
+
+    /// Returns an upper bound on the number of values yielded by
+    /// `Shrink::shrink`, if one can be computed cheaply.
+    ///
+    /// This is purely informational. It lets the runner report how far
+    /// along it is when shrinking a failure. The default returns `None`.
+    fn shrink_count(&self) -> Option<usize> {
+        None
     }
 }
 
 impl Arbitrary for () {
     fn arbitrary<G: Gen>(_: &mut G) -> () { () }
+    fn shrink_count(&self) -> Option<usize> { Some(0) }
+}
+
+impl Shrink for () {}
+
+impl<A: ?Sized + Send + 'static> Arbitrary for PhantomData<A> {
+    fn arbitrary<G: Gen>(_: &mut G) -> PhantomData<A> { PhantomData }
+    fn shrink_count(&self) -> Option<usize> { Some(0) }
 }
 
+impl<A: ?Sized + Send + 'static> Shrink for PhantomData<A> {}
+
 impl Arbitrary for bool {
     fn arbitrary<G: Gen>(g: &mut G) -> bool { g.gen() }
+    fn shrink_count(&self) -> Option<usize> {
+        Some(if *self { 1 } else { 0 })
+    }
+}
+
+impl Shrink for bool {
     fn shrink(&self) -> Box<Iterator<Item=bool>+'static> {
         match *self {
             true => single_shrinker(false),
@@ -110,6 +597,41 @@ impl Arbitrary for bool {
     }
 }
 
+impl Arbitrary for Ordering {
+    fn arbitrary<G: Gen>(g: &mut G) -> Ordering {
+        *g.choose(&[Ordering::Less, Ordering::Equal, Ordering::Greater])
+          .unwrap()
+    }
+    fn shrink_count(&self) -> Option<usize> {
+        Some(if *self == Ordering::Equal { 0 } else { 1 })
+    }
+}
+
+impl Shrink for Ordering {
+    fn shrink(&self) -> Box<Iterator<Item=Ordering>+'static> {
+        match *self {
+            Ordering::Equal => empty_shrinker(),
+            _ => single_shrinker(Ordering::Equal),
+        }
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for Reverse<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Reverse<A> {
+        Reverse(Arbitrary::arbitrary(g))
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        self.0.shrink_count()
+    }
+}
+
+impl<A: Shrink> Shrink for Reverse<A> {
+    fn shrink(&self) -> Box<Iterator<Item=Reverse<A>>+'static> {
+        Box::new(self.0.shrink().map(Reverse))
+    }
+}
+
 impl<A: Arbitrary> Arbitrary for Option<A> {
     fn arbitrary<G: Gen>(g: &mut G) -> Option<A> {
         if g.gen() {
@@ -119,6 +641,15 @@ impl<A: Arbitrary> Arbitrary for Option<A> {
         }
     }
 
+    fn shrink_count(&self) -> Option<usize> {
+        match *self {
+            None => Some(0),
+            Some(ref x) => x.shrink_count().map(|n| n + 1),
+        }
+    }
+}
+
+impl<A: Shrink> Shrink for Option<A> {
     fn shrink(&self)  -> Box<Iterator<Item=Option<A>>+'static> {
         match *self {
             None => {
@@ -132,6 +663,57 @@ impl<A: Arbitrary> Arbitrary for Option<A> {
     }
 }
 
+/// An `Option` that is `None` only one time in ten.
+///
+/// The `Arbitrary` impl for `Option` produces `None` half of the time, which
+/// means nested optional structures are rarely populated.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SomeBiased<A>(pub Option<A>);
+
+/// An `Option` that is `None` nine times in ten.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NoneBiased<A>(pub Option<A>);
+
+impl<A: Arbitrary> Arbitrary for SomeBiased<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> SomeBiased<A> {
+        SomeBiased(gen_option(g, 0.1))
+    }
+
+    fn shrink_count(&self) -> Option<usize> { self.0.shrink_count() }
+}
+
+impl<A: Shrink> Shrink for SomeBiased<A> {
+    fn shrink(&self) -> Box<Iterator<Item=SomeBiased<A>>+'static> {
+        Box::new(self.0.shrink().map(SomeBiased))
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for NoneBiased<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> NoneBiased<A> {
+        NoneBiased(gen_option(g, 0.9))
+    }
+
+    fn shrink_count(&self) -> Option<usize> { self.0.shrink_count() }
+}
+
+impl<A: Shrink> Shrink for NoneBiased<A> {
+    fn shrink(&self) -> Box<Iterator<Item=NoneBiased<A>>+'static> {
+        Box::new(self.0.shrink().map(NoneBiased))
+    }
+}
+
+/// Generates `None` with probability `p` and `Some` otherwise.
+///
+/// A `p` of `0.0` always produces `Some` and a `p` of `1.0` always produces
+/// `None`.
+fn gen_option<G: Gen, A: Arbitrary>(g: &mut G, p: f64) -> Option<A> {
+    if g.gen::<f64>() < p {
+        None
+    } else {
+        Some(Arbitrary::arbitrary(g))
+    }
+}
+
 impl<A: Arbitrary, B: Arbitrary> Arbitrary for Result<A, B> {
     fn arbitrary<G: Gen>(g: &mut G) -> Result<A, B> {
         if g.gen() {
@@ -141,6 +723,15 @@ impl<A: Arbitrary, B: Arbitrary> Arbitrary for Result<A, B> {
         }
     }
 
+    fn shrink_count(&self) -> Option<usize> {
+        match *self {
+            Ok(ref x) => x.shrink_count(),
+            Err(ref x) => x.shrink_count(),
+        }
+    }
+}
+
+impl<A: Shrink, B: Shrink> Shrink for Result<A, B> {
     fn shrink(&self) -> Box<Iterator<Item=Result<A, B>>+'static> {
         match *self {
             Ok(ref x) => {
@@ -157,6 +748,166 @@ impl<A: Arbitrary, B: Arbitrary> Arbitrary for Result<A, B> {
     }
 }
 
+impl<A: Arbitrary + Sync> Arbitrary for Arc<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Arc<A> {
+        Arc::new(Arbitrary::arbitrary(g))
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        (**self).shrink_count()
+    }
+}
+
+impl<A: Shrink> Shrink for Arc<A> {
+    fn shrink(&self) -> Box<Iterator<Item=Arc<A>>+'static> {
+        Box::new((**self).shrink().map(Arc::new))
+    }
+}
+
+/// Always generated and shrunk as `Cow::Owned`.
+impl<B> Arbitrary for Cow<'static, B>
+        where B: ToOwned + ?Sized + Sync + 'static,
+              <B as ToOwned>::Owned: Arbitrary {
+    fn arbitrary<G: Gen>(g: &mut G) -> Cow<'static, B> {
+        Cow::Owned(Arbitrary::arbitrary(g))
+    }
+}
+
+impl<B> Shrink for Cow<'static, B>
+        where B: ToOwned + ?Sized + 'static, <B as ToOwned>::Owned: Shrink {
+    fn shrink(&self) -> Box<Iterator<Item=Cow<'static, B>>+'static> {
+        Box::new(self.clone().into_owned().shrink().map(Cow::Owned))
+    }
+}
+
+/// `Cell` is only `Clone` when its value is `Copy`.
+impl<A: Arbitrary + Copy> Arbitrary for Cell<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Cell<A> {
+        Cell::new(Arbitrary::arbitrary(g))
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        self.get().shrink_count()
+    }
+}
+
+impl<A: Shrink + Copy> Shrink for Cell<A> {
+    fn shrink(&self) -> Box<Iterator<Item=Cell<A>>+'static> {
+        Box::new(self.get().shrink().map(Cell::new))
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for RefCell<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> RefCell<A> {
+        RefCell::new(Arbitrary::arbitrary(g))
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        self.borrow().shrink_count()
+    }
+}
+
+impl<A: Shrink> Shrink for RefCell<A> {
+    fn shrink(&self) -> Box<Iterator<Item=RefCell<A>>+'static> {
+        Box::new(self.borrow().shrink().map(RefCell::new))
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for Box<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Box<A> {
+        Box::new(Arbitrary::arbitrary(g))
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        (**self).shrink_count()
+    }
+}
+
+impl<A: Shrink> Shrink for Box<A> {
+    fn shrink(&self) -> Box<Iterator<Item=Box<A>>+'static> {
+        Box::new((**self).shrink().map(Box::new))
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for Wrapping<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Wrapping<A> {
+        Wrapping(Arbitrary::arbitrary(g))
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        self.0.shrink_count()
+    }
+}
+
+impl<A: Shrink> Shrink for Wrapping<A> {
+    fn shrink(&self) -> Box<Iterator<Item=Wrapping<A>>+'static> {
+        Box::new(self.0.shrink().map(Wrapping))
+    }
+}
+
+/// Ranges always have `start <= end`, but they can be empty.
+impl<A: Arbitrary + PartialOrd> Arbitrary for Range<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Range<A> {
+        let (a, b): (A, A) = Arbitrary::arbitrary(g);
+        if a <= b { a..b } else { b..a }
+    }
+}
+
+impl<A: Shrink + Clone + PartialOrd> Shrink for Range<A> {
+    fn shrink(&self) -> Box<Iterator<Item=Range<A>>+'static> {
+        let bounds = (self.start.clone(), self.end.clone());
+        Box::new(bounds.shrink().filter(|&(ref a, ref b)| a <= b)
+                                .map(|(a, b)| a..b))
+    }
+}
+
+/// Inclusive ranges always have `start <= end`, so they are never empty.
+impl<A: Arbitrary + PartialOrd> Arbitrary for RangeInclusive<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> RangeInclusive<A> {
+        let (a, b): (A, A) = Arbitrary::arbitrary(g);
+        if a <= b { a..=b } else { b..=a }
+    }
+}
+
+impl<A: Shrink + Clone + PartialOrd> Shrink for RangeInclusive<A> {
+    fn shrink(&self) -> Box<Iterator<Item=RangeInclusive<A>>+'static> {
+        let bounds = (self.start().clone(), self.end().clone());
+        Box::new(bounds.shrink().filter(|&(ref a, ref b)| a <= b)
+                                .map(|(a, b)| a..=b))
+    }
+}
+
+/// `Included` and `Excluded` shrink to `Unbounded` first and then shrink
+/// their value.
+impl<A: Arbitrary> Arbitrary for Bound<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Bound<A> {
+        match g.gen_range(0, 3) {
+            0 => Bound::Included(Arbitrary::arbitrary(g)),
+            1 => Bound::Excluded(Arbitrary::arbitrary(g)),
+            _ => Bound::Unbounded,
+        }
+    }
+}
+
+impl<A: Shrink> Shrink for Bound<A> {
+    fn shrink(&self) -> Box<Iterator<Item=Bound<A>>+'static> {
+        let unbounded = Some(Bound::Unbounded).into_iter();
+        match *self {
+            Bound::Included(ref x) => {
+                Box::new(unbounded.chain(x.shrink().map(Bound::Included)))
+            }
+            Bound::Excluded(ref x) => {
+                Box::new(unbounded.chain(x.shrink().map(Bound::Excluded)))
+            }
+            Bound::Unbounded => empty_shrinker(),
+        }
+    }
+}
+
+macro_rules! try_opt {
+    ($e:expr) => (match $e { Some(e) => e, None => return None })
+}
+
 macro_rules! impl_arb_for_tuple {
     (($var_a:ident, $type_a:ident) $(, ($var_n:ident, $type_n:ident))*) => (
         impl<$type_a: Arbitrary, $($type_n: Arbitrary),*> Arbitrary
@@ -172,6 +923,16 @@ macro_rules! impl_arb_for_tuple {
                 )
             }
 
+            fn shrink_count(&self) -> Option<usize> {
+                let (ref $var_a, $(ref $var_n),*) = *self;
+                let n = try_opt!($var_a.shrink_count());
+                $(let n = n + try_opt!($var_n.shrink_count());)*
+                Some(n)
+            }
+        }
+
+        impl<$type_a: Shrink + Clone, $($type_n: Shrink + Clone),*> Shrink
+                for ($type_a, $($type_n),*) {
             fn shrink(&self)
                      -> Box<Iterator<Item=($type_a, $($type_n),*)> + 'static> {
                 let (ref $var_a, $(ref $var_n),*) = *self;
@@ -208,40 +969,278 @@ impl_arb_for_tuple!((a, A), (b, B), (c, C), (d, D), (e, E), (f, F),
                     (g, G), (h, H), (i, I), (j, J), (k, K));
 impl_arb_for_tuple!((a, A), (b, B), (c, C), (d, D), (e, E), (f, F),
                     (g, G), (h, H), (i, I), (j, J), (k, K), (l, L));
+impl_arb_for_tuple!((a, A), (b, B), (c, C), (d, D), (e, E), (f, F),
+                    (g, G), (h, H), (i, I), (j, J), (k, K), (l, L),
+                    (m, M));
+impl_arb_for_tuple!((a, A), (b, B), (c, C), (d, D), (e, E), (f, F),
+                    (g, G), (h, H), (i, I), (j, J), (k, K), (l, L),
+                    (m, M), (n, N));
+impl_arb_for_tuple!((a, A), (b, B), (c, C), (d, D), (e, E), (f, F),
+                    (g, G), (h, H), (i, I), (j, J), (k, K), (l, L),
+                    (m, M), (n, N), (o, O));
+impl_arb_for_tuple!((a, A), (b, B), (c, C), (d, D), (e, E), (f, F),
+                    (g, G), (h, H), (i, I), (j, J), (k, K), (l, L),
+                    (m, M), (n, N), (o, O), (p, P));
+
+// Implements `Arbitrary` for arrays of every length from `$n` down to zero.
+// There must be `$n` copies of the element type `A` after `$n`.
+macro_rules! impl_arb_for_arrays {
+    ($n:expr, $t:ident $($ts:ident)*) => {
+        impl<A: Arbitrary> Arbitrary for [A; $n] {
+            fn arbitrary<G: Gen>(g: &mut G) -> [A; $n] {
+                [Arbitrary::arbitrary(g), $({
+                    let arb: $ts = Arbitrary::arbitrary(g);
+                    arb
+                }),*]
+            }
+
+            fn shrink_count(&self) -> Option<usize> {
+                let mut n = 0;
+                for x in self.iter() {
+                    n += try_opt!(x.shrink_count());
+                }
+                Some(n)
+            }
+        }
+
+        impl<A: Shrink + Clone> Shrink for [A; $n] {
+            fn shrink(&self) -> Box<Iterator<Item=[A; $n]>+'static> {
+                // Shrink every element in place, like `Vec` does after
+                // trying to remove elements.
+                let mut xs = vec![];
+                for (i, x) in self.iter().enumerate() {
+                    for sx in x.shrink() {
+                        let mut change_one = self.clone();
+                        change_one[i] = sx;
+                        xs.push(change_one);
+                    }
+                }
+                Box::new(xs.into_iter())
+            }
+        }
+        impl_arb_for_arrays!($n - 1, $($ts)*);
+    };
+    ($n:expr,) => {
+        impl<A: Arbitrary> Arbitrary for [A; 0] {
+            fn arbitrary<G: Gen>(_: &mut G) -> [A; 0] { [] }
+            fn shrink_count(&self) -> Option<usize> { Some(0) }
+        }
+
+        impl<A: 'static> Shrink for [A; 0] {}
+    };
+}
+
+impl_arb_for_arrays!(32, A A A A A A A A A A A A A A A A
+                         A A A A A A A A A A A A A A A A);
 
 impl<A: Arbitrary> Arbitrary for Vec<A> {
     fn arbitrary<G: Gen>(g: &mut G) -> Vec<A> {
-        let size = { let s = g.size(); g.gen_range(0, s) };
+        let size = gen_len(g);
         (0..size).map(|_| Arbitrary::arbitrary(g)).collect()
     }
 
-    fn shrink(&self) -> Box<Iterator<Item=Vec<A>>+'static> {
+    fn shrink_count(&self) -> Option<usize> {
         if self.len() == 0 {
-            return empty_shrinker();
+            return Some(0);
         }
-
-        // Start the shrunk values with an empty vector.
-        let mut xs: Vec<Vec<A>> = vec![vec![]];
-
-        // Explore the space of different sized vectors without shrinking
-        // any of the elements.
+        // One empty vector, the short prefixes, `len / k` removals for each
+        // chunk size `k` and then the shrunk values of every element.
+        let mut n = 1 + short_prefixes(self.len()).len();
         let mut k = self.len() / 2;
         while k > 0 {
-            xs.extend(shuffle_vec(&**self, k).into_iter());
+            n += self.len() / k;
             k = k / 2;
         }
+        for x in self.iter() {
+            n += try_opt!(x.shrink_count());
+        }
+        Some(n)
+    }
+}
 
-        // Now explore the space of vectors where each element is shrunk
-        // in turn. A new vector is generated for each shrunk value of each
-        // element.
-        for (i, x) in self.iter().enumerate() {
-            for sx in x.shrink() {
-                let mut change_one = self.clone();
-                change_one[i] = sx;
-                xs.push(change_one);
-            }
+impl<A: Shrink + Clone> Shrink for Vec<A> {
+    fn shrink(&self) -> Box<Iterator<Item=Vec<A>>+'static> {
+        if self.len() == 0 {
+            return empty_shrinker();
         }
-        Box::new(xs.into_iter())
+        Box::new(VecShrinker::new(self.clone()))
+    }
+}
+
+/// A vector with at least one element.
+///
+/// Its length is picked like the length of a `Vec`, except that it is at
+/// least one even if the minimum size of the generator is zero. Shrinking
+/// is the same as for `Vec`, except that the empty vector is skipped.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NonEmptyVec<A>(pub Vec<A>);
+
+impl<A: Arbitrary> Arbitrary for NonEmptyVec<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> NonEmptyVec<A> {
+        let min = cmp::max(1, g.min_size());
+        let len = { let s = g.size(); g.gen_range(min, cmp::max(s, min + 1)) };
+        NonEmptyVec((0..len).map(|_| Arbitrary::arbitrary(g)).collect())
+    }
+}
+
+impl<A: Shrink + Clone> Shrink for NonEmptyVec<A> {
+    fn shrink(&self) -> Box<Iterator<Item=NonEmptyVec<A>>+'static> {
+        Box::new(self.0.shrink().filter(|xs| xs.len() > 0).map(NonEmptyVec))
+    }
+}
+
+/// A buffer of bytes, generated all at once with `Gen::gen_bytes`.
+///
+/// Unlike the bytes in a `Vec<u8>`, which are less than the size of the
+/// generator, the bytes can have any value. The length is chosen like the
+/// length of a `Vec`, and shrinking is the same as for `Vec<u8>`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RawBytes(pub Vec<u8>);
+
+impl Arbitrary for RawBytes {
+    fn arbitrary<G: Gen>(g: &mut G) -> RawBytes {
+        let len = gen_len(g);
+        RawBytes(g.gen_bytes(len))
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        self.0.shrink_count()
+    }
+}
+
+impl Shrink for RawBytes {
+    fn shrink(&self) -> Box<Iterator<Item=RawBytes>+'static> {
+        Box::new(self.0.shrink().map(RawBytes))
+    }
+}
+
+/// A vector whose elements are in ascending order.
+///
+/// Both generated vectors and their shrinks are sorted after the fact, so
+/// shrinking is the same as for `Vec` otherwise.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sorted<T>(pub T);
+
+impl<T> Deref for Sorted<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.0 }
+}
+
+impl<A: Arbitrary + Ord> Arbitrary for Sorted<Vec<A>> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Sorted<Vec<A>> {
+        let mut xs: Vec<A> = Arbitrary::arbitrary(g);
+        xs.sort();
+        Sorted(xs)
+    }
+}
+
+impl<A: Shrink + Clone + Ord> Shrink for Sorted<Vec<A>> {
+    fn shrink(&self) -> Box<Iterator<Item=Sorted<Vec<A>>>+'static> {
+        Box::new(self.0.shrink().map(|mut xs| { xs.sort(); Sorted(xs) }))
+    }
+}
+
+/// A vector without repeated elements.
+///
+/// Both generated vectors and their shrinks keep only the first of equal
+/// elements, so shrinking is the same as for `Vec` otherwise.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Unique<T>(pub T);
+
+impl<T> Deref for Unique<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.0 }
+}
+
+impl<A: Arbitrary + Ord> Arbitrary for Unique<Vec<A>> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Unique<Vec<A>> {
+        Unique(dedup(Arbitrary::arbitrary(g)))
+    }
+}
+
+impl<A: Shrink + Clone + Ord> Shrink for Unique<Vec<A>> {
+    fn shrink(&self) -> Box<Iterator<Item=Unique<Vec<A>>>+'static> {
+        Box::new(self.0.shrink().map(|xs| Unique(dedup(xs))))
+    }
+}
+
+/// Removes all but the first of equal elements from `xs`.
+fn dedup<A: Clone + Ord>(xs: Vec<A>) -> Vec<A> {
+    let mut seen = BTreeSet::new();
+    xs.into_iter().filter(|x| seen.insert(x.clone())).collect()
+}
+
+impl<A: Arbitrary> Arbitrary for VecDeque<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> VecDeque<A> {
+        let vec: Vec<A> = Arbitrary::arbitrary(g);
+        vec.into_iter().collect()
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        let vec: Vec<A> = self.iter().cloned().collect();
+        vec.shrink_count()
+    }
+}
+
+impl<A: Shrink + Clone> Shrink for VecDeque<A> {
+    fn shrink(&self) -> Box<Iterator<Item=VecDeque<A>>+'static> {
+        let vec: Vec<A> = self.iter().cloned().collect();
+        Box::new(vec.shrink().map(|v| v.into_iter().collect()))
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for LinkedList<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> LinkedList<A> {
+        let vec: Vec<A> = Arbitrary::arbitrary(g);
+        vec.into_iter().collect()
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        let vec: Vec<A> = self.iter().cloned().collect();
+        vec.shrink_count()
+    }
+}
+
+impl<A: Shrink + Clone> Shrink for LinkedList<A> {
+    fn shrink(&self) -> Box<Iterator<Item=LinkedList<A>>+'static> {
+        let vec: Vec<A> = self.iter().cloned().collect();
+        Box::new(vec.shrink().map(|v| v.into_iter().collect()))
+    }
+}
+
+/// Shrinking starts from the elements in ascending order.
+impl<A: Arbitrary + Ord> Arbitrary for BinaryHeap<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> BinaryHeap<A> {
+        let vec: Vec<A> = Arbitrary::arbitrary(g);
+        vec.into_iter().collect()
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        self.clone().into_sorted_vec().shrink_count()
+    }
+}
+
+impl<A: Shrink + Clone + Ord> Shrink for BinaryHeap<A> {
+    fn shrink(&self) -> Box<Iterator<Item=BinaryHeap<A>>+'static> {
+        let vec = self.clone().into_sorted_vec();
+        Box::new(vec.shrink().map(|v| v.into_iter().collect()))
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for Box<[A]> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Box<[A]> {
+        let vec: Vec<A> = Arbitrary::arbitrary(g);
+        vec.into_boxed_slice()
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        self.to_vec().shrink_count()
+    }
+}
+
+impl<A: Shrink + Clone> Shrink for Box<[A]> {
+    fn shrink(&self) -> Box<Iterator<Item=Box<[A]>>+'static> {
+        Box::new(self.to_vec().shrink().map(|v| v.into_boxed_slice()))
     }
 }
 
@@ -251,7 +1250,10 @@ impl<A: Arbitrary> Arbitrary for TrieMap<A> {
         let vec: Vec<(usize, A)> = Arbitrary::arbitrary(g);
         vec.into_iter().collect()
     }
+}
 
+#[cfg(feature = "collect_impls")]
+impl<A: Shrink + Clone> Shrink for TrieMap<A> {
     fn shrink(&self) -> Box<Iterator<Item=TrieMap<A>>+'static> {
         let vec: Vec<(usize, A)> = self.iter()
                                       .map(|(a, b)| (a, b.clone()))
@@ -260,235 +1262,2069 @@ impl<A: Arbitrary> Arbitrary for TrieMap<A> {
     }
 }
 
-impl<K: Arbitrary + Eq + Hash, V: Arbitrary> Arbitrary for HashMap<K, V> {
-    fn arbitrary<G: Gen>(g: &mut G) -> HashMap<K, V> {
-        let vec: Vec<(K, V)> = Arbitrary::arbitrary(g);
-        vec.into_iter().collect()
+#[cfg(feature = "bytes")]
+impl Arbitrary for Bytes {
+    fn arbitrary<G: Gen>(g: &mut G) -> Bytes {
+        let vec: Vec<u8> = Arbitrary::arbitrary(g);
+        if vec.len() == 0 || g.gen() {
+            return Bytes::from(vec);
+        }
+        // Return a slice of a larger buffer, so that the result shares its
+        // storage with another handle.
+        let start = g.gen_range(0, vec.len());
+        let end = g.gen_range(start, vec.len() + 1);
+        Bytes::from(vec).slice(start, end)
     }
 
-    fn shrink(&self) -> Box<Iterator<Item=HashMap<K, V>>+'static> {
-        let vec: Vec<(K, V)> = self.clone().into_iter().collect();
-        Box::new(vec.shrink().map(|v| v.into_iter().collect::<HashMap<K, V>>()))
-    }
+    fn shrink_count(&self) -> Option<usize> { self.to_vec().shrink_count() }
 }
 
-impl Arbitrary for String {
-    fn arbitrary<G: Gen>(g: &mut G) -> String {
-        let size = { let s = g.size(); g.gen_range(0, s) };
-        g.gen_ascii_chars().take(size).collect()
+#[cfg(feature = "bytes")]
+impl Shrink for Bytes {
+    fn shrink(&self) -> Box<Iterator<Item=Bytes>+'static> {
+        Box::new(self.to_vec().shrink().map(Bytes::from))
     }
+}
 
-    fn shrink(&self) -> Box<Iterator<Item=String>+'static> {
-        // Shrink a string by shrinking a vector of its characters.
-        let chars: Vec<char> = self.chars().collect();
-        Box::new(chars.shrink().map(|x| x.into_iter().collect::<String>()))
+#[cfg(feature = "bytes")]
+impl Arbitrary for BytesMut {
+    fn arbitrary<G: Gen>(g: &mut G) -> BytesMut {
+        let vec: Vec<u8> = Arbitrary::arbitrary(g);
+        BytesMut::from(vec)
     }
+
+    fn shrink_count(&self) -> Option<usize> { self.to_vec().shrink_count() }
 }
 
-impl Arbitrary for char {
-    fn arbitrary<G: Gen>(g: &mut G) -> char { g.gen() }
+#[cfg(feature = "bytes")]
+impl Shrink for BytesMut {
+    fn shrink(&self) -> Box<Iterator<Item=BytesMut>+'static> {
+        Box::new(self.to_vec().shrink().map(BytesMut::from))
+    }
+}
 
-    fn shrink(&self) -> Box<Iterator<Item=char>+'static> {
-        // No char shrinking for now.
-        empty_shrinker()
+#[cfg(feature = "rust_decimal")]
+impl Arbitrary for Decimal {
+    fn arbitrary<G: Gen>(g: &mut G) -> Decimal {
+        // A `Decimal` has a 96 bit mantissa and a scale of at most 28.
+        let bits = g.gen_range(0, 97);
+        let mut mantissa = (g.gen::<u64>() as i128)
+                           | ((g.gen::<u32>() as i128) << 64);
+        mantissa &= (1 << bits) - 1;
+        if g.gen() {
+            mantissa = -mantissa;
+        }
+        Decimal::from_i128_with_scale(mantissa, g.gen_range(0, 29))
     }
 }
 
-/// Returns a sequence of vectors with each contiguous run of elements of
-/// length `k` removed.
-fn shuffle_vec<A: Clone>(xs: &[A], k: usize) -> Vec<Vec<A>> {
-    fn shuffle<A: Clone>(xs: &[A], k: usize, n: usize) -> Vec<Vec<A>> {
-        if k > n {
-            return vec![];
+#[cfg(feature = "rust_decimal")]
+impl Shrink for Decimal {
+    fn shrink(&self) -> Box<Iterator<Item=Decimal>+'static> {
+        let (mantissa, scale) = (self.mantissa(), self.scale());
+        if mantissa == 0 {
+            return empty_shrinker();
         }
-        let xs1: Vec<A> = xs[..k].iter().map(|x| x.clone()).collect();
-        let xs2: Vec<A> = xs[k..].iter().map(|x| x.clone()).collect();
-        if xs2.len() == 0 {
-            return vec![vec![]];
+        // Shrink the mantissa the same way as signed integers, then try
+        // the same mantissa with fewer digits after the decimal point.
+        let mut mantissas = vec![0];
+        if mantissa < 0 {
+            mantissas.push(-mantissa);
         }
+        let mut i = mantissa / 2;
+        while i != 0 {
+            mantissas.push(mantissa - i);
+            i /= 2;
+        }
+        let mut xs: Vec<Decimal> =
+            mantissas.into_iter()
+                     .map(|m| Decimal::from_i128_with_scale(m, scale))
+                     .collect();
+        xs.extend((0..scale).map(|s| {
+            Decimal::from_i128_with_scale(mantissa, s)
+        }));
+        Box::new(xs.into_iter())
+    }
+}
 
-        let cat = |x: &Vec<A>| {
-            let mut pre = xs1.clone();
-            pre.extend(x.clone().into_iter());
-            pre
-        };
-        let shuffled = shuffle(&*xs2, k, n-k);
-        let mut more: Vec<Vec<A>> = shuffled.iter().map(cat).collect();
-        more.insert(0, xs2);
-        more
+/// Generates distinct values, using `insert` to tell whether a value has
+/// not been seen before.
+///
+/// This is how sets get as many elements as the length picked by
+/// `gen_len`, instead of fewer when some generated elements are equal.
+fn unique_values<G, A, F>(g: &mut G, mut insert: F) -> Vec<A>
+        where G: Gen, A: Arbitrary, F: FnMut(&A) -> bool {
+    let size = gen_len(g);
+    let mut xs = Vec::with_capacity(size);
+    // `A` may have fewer than `size` values, so give up eventually.
+    for _ in 0..size * 10 {
+        if xs.len() == size {
+            break;
+        }
+        let x: A = Arbitrary::arbitrary(g);
+        if insert(&x) {
+            xs.push(x);
+        }
     }
-    shuffle(xs, k, xs.len())
+    xs
 }
 
-macro_rules! unsigned_shrinker {
-    ($ty:ty) => {
-        mod shrinker {
-            pub struct UnsignedShrinker {
-                x: $ty,
-                i: $ty,
-            }
+/// Generates entries with distinct keys, using `insert` to tell whether a
+/// key has not been seen before.
+///
+/// The keys are picked first and only then paired with values, so that
+/// collecting the entries into a map never drops any of them.
+fn unique_entries<G, K, V, F>(g: &mut G, insert: F) -> Vec<(K, V)>
+        where G: Gen, K: Arbitrary, V: Arbitrary, F: FnMut(&K) -> bool {
+    let keys = unique_values(g, insert);
+    keys.into_iter().map(|k| (k, Arbitrary::arbitrary(g))).collect()
+}
 
-            impl UnsignedShrinker {
-                pub fn new(x: $ty) -> Box<Iterator<Item=$ty>+'static> {
-                    if x == 0 {
-                        super::empty_shrinker()
-                    } else {
-                        Box::new(vec![0].into_iter().chain(
-                            UnsignedShrinker {
-                                x: x,
-                                i: x / 2,
-                            }
-                        ))
-                    }
-                }
-            }
+impl<K: Arbitrary + Ord, V: Arbitrary> Arbitrary for BTreeMap<K, V> {
+    fn arbitrary<G: Gen>(g: &mut G) -> BTreeMap<K, V> {
+        let mut seen = BTreeSet::new();
+        unique_entries(g, |k: &K| seen.insert(k.clone()))
+            .into_iter().collect()
+    }
 
-            impl Iterator for UnsignedShrinker {
-                type Item = $ty;
-                fn next(&mut self) -> Option<$ty> {
-                    if self.x - self.i < self.x {
-                        let result = Some(self.x - self.i);
-                        self.i = self.i / 2;
-                        result
-                    } else {
-                        None
-                    }
-                }
-            }
-        }
+    fn shrink_count(&self) -> Option<usize> {
+        let vec: Vec<(K, V)> = self.clone().into_iter().collect();
+        vec.shrink_count()
     }
 }
 
-macro_rules! unsigned_arbitrary {
-    ($($ty:ty),*) => {
-        $(
-            impl Arbitrary for $ty {
-                fn arbitrary<G: Gen>(g: &mut G) -> $ty {
-                    #![allow(trivial_numeric_casts)]
-                    let s = g.size(); g.gen_range(0, s as $ty)
-                }
-                fn shrink(&self) -> Box<Iterator<Item=$ty>+'static> {
-                    unsigned_shrinker!($ty);
-                    shrinker::UnsignedShrinker::new(*self)
-                }
-            }
-        )*
+impl<K, V> Shrink for BTreeMap<K, V>
+        where K: Shrink + Clone + Ord, V: Shrink + Clone {
+    fn shrink(&self) -> Box<Iterator<Item=BTreeMap<K, V>>+'static> {
+        let vec: Vec<(K, V)> = self.clone().into_iter().collect();
+        Box::new(vec.shrink().map(|v| v.into_iter().collect()))
     }
 }
 
-unsigned_arbitrary! {
-    usize, u8, u16, u32, u64
+// The hash maps and sets are generic over the hasher, so that maps and
+// sets with a custom hasher can be generated too. New ones are created
+// with `S::default()`.
+
+impl<K, V, S> Arbitrary for HashMap<K, V, S>
+        where K: Arbitrary + Eq + Hash, V: Arbitrary,
+              S: BuildHasher + Default + Clone + Send + 'static {
+    fn arbitrary<G: Gen>(g: &mut G) -> HashMap<K, V, S> {
+        let mut seen = HashSet::new();
+        unique_entries(g, |k: &K| seen.insert(k.clone()))
+            .into_iter().collect()
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        let vec: Vec<(K, V)> = self.clone().into_iter().collect();
+        vec.shrink_count()
+    }
 }
 
-macro_rules! signed_shrinker {
-    ($ty:ty) => {
-        mod shrinker {
-            pub struct SignedShrinker {
-                x: $ty,
-                i: $ty,
-            }
+impl<K, V, S> Shrink for HashMap<K, V, S>
+        where K: Shrink + Clone + Eq + Hash, V: Shrink + Clone,
+              S: BuildHasher + Default + Clone + 'static {
+    fn shrink(&self) -> Box<Iterator<Item=HashMap<K, V, S>>+'static> {
+        let vec: Vec<(K, V)> = self.clone().into_iter().collect();
+        Box::new(vec.shrink().map(|v| v.into_iter().collect()))
+    }
+}
 
-            impl SignedShrinker {
-                pub fn new(x: $ty) -> Box<Iterator<Item=$ty>+'static> {
-                    if x == 0 {
-                        super::empty_shrinker()
-                    } else {
-                        let shrinker = SignedShrinker {
-                            x: x,
-                            i: x / 2,
-                        };
-                        let mut items = vec![0];
-                        if shrinker.i < 0 {
-                            items.push(shrinker.x.abs());
-                        }
-                        Box::new(items.into_iter().chain(shrinker))
-                    }
-                }
-            }
+// Sets are shrunk as vectors. Shrinking a vector can make elements equal,
+// so a shrunk set may lose more than one element.
 
-            impl Iterator for SignedShrinker {
-                type Item = $ty;
-                fn next(&mut self) -> Option<$ty> {
-                    if (self.x - self.i).abs() < self.x.abs() {
-                        let result = Some(self.x - self.i);
-                        self.i = self.i / 2;
-                        result
-                    } else {
-                        None
-                    }
-                }
-            }
-        }
+impl<A: Arbitrary + Ord> Arbitrary for BTreeSet<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> BTreeSet<A> {
+        let mut seen = BTreeSet::new();
+        unique_values(g, |x: &A| seen.insert(x.clone())).into_iter().collect()
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        let vec: Vec<A> = self.iter().cloned().collect();
+        vec.shrink_count()
     }
 }
 
-macro_rules! signed_arbitrary {
-    ($($ty:ty),*) => {
-        $(
-            impl Arbitrary for $ty {
-                fn arbitrary<G: Gen>(g: &mut G) -> $ty {
-                    let s = g.size(); g.gen_range(-(s as $ty), s as $ty)
-                }
-                fn shrink(&self) -> Box<Iterator<Item=$ty>+'static> {
-                    signed_shrinker!($ty);
-                    shrinker::SignedShrinker::new(*self)
-                }
-            }
-        )*
+impl<A: Shrink + Clone + Ord> Shrink for BTreeSet<A> {
+    fn shrink(&self) -> Box<Iterator<Item=BTreeSet<A>>+'static> {
+        let vec: Vec<A> = self.iter().cloned().collect();
+        Box::new(vec.shrink().map(|v| v.into_iter().collect()))
     }
 }
 
-signed_arbitrary! {
-    isize, i8, i16, i32, i64
+impl<A, S> Arbitrary for HashSet<A, S>
+        where A: Arbitrary + Eq + Hash,
+              S: BuildHasher + Default + Clone + Send + 'static {
+    fn arbitrary<G: Gen>(g: &mut G) -> HashSet<A, S> {
+        let mut seen = HashSet::new();
+        unique_values(g, |x: &A| seen.insert(x.clone())).into_iter().collect()
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        let vec: Vec<A> = self.iter().cloned().collect();
+        vec.shrink_count()
+    }
 }
 
-impl Arbitrary for f32 {
-    fn arbitrary<G: Gen>(g: &mut G) -> f32 {
-        let s = g.size(); g.gen_range(-(s as f32), s as f32)
+impl<A, S> Shrink for HashSet<A, S>
+        where A: Shrink + Clone + Eq + Hash,
+              S: BuildHasher + Default + Clone + 'static {
+    fn shrink(&self) -> Box<Iterator<Item=HashSet<A, S>>+'static> {
+        let vec: Vec<A> = self.iter().cloned().collect();
+        Box::new(vec.shrink().map(|v| v.into_iter().collect()))
+    }
+}
+
+impl Arbitrary for String {
+    fn arbitrary<G: Gen>(g: &mut G) -> String {
+        let size = gen_len(g);
+        GenRng(g).gen_ascii_chars().take(size).collect()
+    }
+
+    fn shrink_count(&self) -> Option<usize> {
+        let chars: Vec<char> = self.chars().collect();
+        chars.shrink_count()
     }
-    fn shrink(&self) -> Box<Iterator<Item=f32>+'static> {
-        signed_shrinker!(i32);
-        let it = shrinker::SignedShrinker::new(*self as i32);
-        Box::new(it.map(|x| x as f32))
+}
+
+impl Shrink for String {
+    fn shrink(&self) -> Box<Iterator<Item=String>+'static> {
+        // Shrink a string by shrinking a vector of its characters.
+        let chars: Vec<char> = self.chars().collect();
+        Box::new(chars.shrink().map(|x| x.into_iter().collect::<String>()))
     }
 }
 
-impl Arbitrary for f64 {
-    fn arbitrary<G: Gen>(g: &mut G) -> f64 {
-        let s = g.size(); g.gen_range(-(s as f64), s as f64)
+impl Arbitrary for Box<str> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Box<str> {
+        let s: String = Arbitrary::arbitrary(g);
+        s.into_boxed_str()
     }
-    fn shrink(&self) -> Box<Iterator<Item=f64>+'static> {
-        signed_shrinker!(i64);
-        let it = shrinker::SignedShrinker::new(*self as i64);
-        Box::new(it.map(|x| x as f64))
+
+    fn shrink_count(&self) -> Option<usize> {
+        self.to_string().shrink_count()
     }
 }
 
-#[cfg(test)]
-mod test {
-    use rand;
-    use std::collections::{HashMap, HashSet};
-    use std::fmt::Debug;
-    use std::hash::Hash;
-    use super::Arbitrary;
+impl Shrink for Box<str> {
+    fn shrink(&self) -> Box<Iterator<Item=Box<str>>+'static> {
+        Box::new(self.to_string().shrink().map(|s| s.into_boxed_str()))
+    }
+}
 
-    #[cfg(feature = "collect_impls")]
-    use collect::TrieMap;
+/// Paths are relative paths built from arbitrary strings. Components that
+/// are empty or that contain a separator or a NUL byte are dropped, so the
+/// number of components of a path is at most the number of strings it was
+/// built from.
+impl Arbitrary for PathBuf {
+    fn arbitrary<G: Gen>(g: &mut G) -> PathBuf {
+        path_from_components(Arbitrary::arbitrary(g))
+    }
+}
+
+impl Shrink for PathBuf {
+    fn shrink(&self) -> Box<Iterator<Item=PathBuf>+'static> {
+        let components: Vec<String> = self.iter()
+            .map(|c| c.to_string_lossy().into_owned())
+            .collect();
+        Box::new(components.shrink().map(path_from_components))
+    }
+}
+
+fn path_from_components(components: Vec<String>) -> PathBuf {
+    components.into_iter()
+        .filter(|c| {
+            !c.is_empty() && !c.chars().any(|ch| {
+                ch == '\0' || path::is_separator(ch)
+            })
+        })
+        .collect()
+}
+
+/// Generated like a `String`. An `OsString` that isn't valid Unicode is
+/// shrunk as if it were the `String` returned by `to_string_lossy`.
+impl Arbitrary for OsString {
+    fn arbitrary<G: Gen>(g: &mut G) -> OsString {
+        let s: String = Arbitrary::arbitrary(g);
+        OsString::from(s)
+    }
+}
+
+impl Shrink for OsString {
+    fn shrink(&self) -> Box<Iterator<Item=OsString>+'static> {
+        let s = self.to_string_lossy().into_owned();
+        Box::new(s.shrink().map(OsString::from))
+    }
+}
+
+/// Generated from a `Vec<u8>` without its NUL bytes. Shrunk byte vectors
+/// that contain a NUL byte are skipped, so bytes shrink toward `1`.
+impl Arbitrary for CString {
+    fn arbitrary<G: Gen>(g: &mut G) -> CString {
+        let bytes: Vec<u8> = Arbitrary::arbitrary(g);
+        let bytes: Vec<u8> = bytes.into_iter().filter(|&b| b != 0).collect();
+        CString::new(bytes).unwrap()
+    }
+}
+
+impl Shrink for CString {
+    fn shrink(&self) -> Box<Iterator<Item=CString>+'static> {
+        let bytes = self.as_bytes().to_vec();
+        Box::new(bytes.shrink()
+                      .filter(|bs| !bs.contains(&0))
+                      .map(|bs| CString::new(bs).unwrap()))
+    }
+}
+
+impl Arbitrary for char {
+    fn arbitrary<G: Gen>(g: &mut G) -> char { g.gen() }
+}
+
+impl Shrink for char {
+    fn shrink(&self) -> Box<Iterator<Item=char>+'static> {
+        // Lowercase letters are the simplest characters, followed by
+        // uppercase letters, digits, a space and then everything else.
+        // Every candidate is simpler than `self`, so shrinking terminates.
+        let c = *self;
+        let mut simpler = vec!['a', 'b', 'c'];
+        if 'A' <= c && c <= 'Z' {
+            simpler.push(((c as u8) + b'a' - b'A') as char);
+        }
+        simpler.extend(['A', 'B', 'C', '1', '2', '3', ' '].iter().cloned());
+        let mut xs: Vec<char> = vec![];
+        for x in simpler {
+            if char_rank(x) < char_rank(c) && !xs.contains(&x) {
+                xs.push(x);
+            }
+        }
+        let code_points = if (c as u32) < 128 {
+            empty_shrinker()
+        } else {
+            (c as u32).shrink()
+        };
+        Box::new(xs.into_iter().chain(
+            code_points.filter_map(char::from_u32)
+                       .filter(move |&x| char_rank(x) < char_rank(c))))
+    }
+}
+
+/// Orders characters from simplest to most complex.
+fn char_rank(c: char) -> (bool, bool, bool, bool, u32) {
+    (!('a' <= c && c <= 'z'), !('A' <= c && c <= 'Z'),
+     !('0' <= c && c <= '9'), c != ' ', c as u32)
+}
+
+/// An ASCII character, including control characters.
+///
+/// Shrinking is the same as for `char`, which never leaves ASCII.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ascii(pub char);
+
+impl Arbitrary for Ascii {
+    fn arbitrary<G: Gen>(g: &mut G) -> Ascii {
+        Ascii(g.gen_range(0u8, 128) as char)
+    }
+}
+
+impl Shrink for Ascii {
+    fn shrink(&self) -> Box<Iterator<Item=Ascii>+'static> {
+        Box::new(self.0.shrink().filter(|&c| (c as u32) < 128).map(Ascii))
+    }
+}
+
+/// A string of ASCII characters, including control characters.
+///
+/// Its length is less than the size of the generator. Shrinking is the
+/// same as for `String`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AsciiString(pub String);
+
+impl Arbitrary for AsciiString {
+    fn arbitrary<G: Gen>(g: &mut G) -> AsciiString {
+        let len = gen_len(g);
+        AsciiString((0..len).map(|_| {
+            g.gen_range(0u8, 128) as char
+        }).collect())
+    }
+}
+
+impl Shrink for AsciiString {
+    fn shrink(&self) -> Box<Iterator<Item=AsciiString>+'static> {
+        Box::new(self.0.shrink()
+                       .filter(|s| s.bytes().all(|b| b < 128))
+                       .map(AsciiString))
+    }
+}
+
+/// Base values of any type, at most one for each type.
+///
+/// See `Fixed`.
+#[derive(Clone, Default)]
+pub struct Fixtures {
+    values: HashMap<TypeId, Arc<Any + Send + Sync>>,
+}
+
+impl Fixtures {
+    /// Returns an empty set of base values.
+    pub fn new() -> Fixtures { Fixtures { values: HashMap::new() } }
+
+    /// Makes `value` the base value of type `T`, replacing the previous
+    /// one.
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) {
+        self.values.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    /// Returns the base value of type `T`, if any.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.values.get(&TypeId::of::<T>()).and_then(|v| v.downcast_ref())
+    }
+}
+
+/// Returns a copy of the base value of type `T` set on `g`.
+///
+/// This is used by `Fixed` and by `#[derive(Arbitrary)]` for fields with
+/// `#[arbitrary(fixed)]`.
+///
+/// # Panics
+///
+/// Panics if `g` has no base value of type `T`.
+#[doc(hidden)]
+pub fn base_value<T: Any + Clone, G: Gen>(g: &G) -> T {
+    match g.fixture::<T>() {
+        Some(v) => v.clone(),
+        None => panic!("no base value was set for a fixed input; set one \
+                        with `StdGen::with_fixture` or \
+                        `QuickCheck::fixture`"),
+    }
+}
+
+/// A wrapper that is always generated as the base value of type `T` and
+/// never shrunk.
+///
+/// The base value is set with `StdGen::with_fixture` or
+/// `QuickCheck::fixture`, and generating a `Fixed<T>` panics if there is
+/// none. This is useful for varying a small part of a large input while
+/// holding the rest of it constant. For example, a property can take a
+/// `(Fixed<Config>, usize)` and build its input with
+/// `Config { retries: n, ..base.0 }`. `#[derive(Arbitrary)]` does the same
+/// for fields marked with `#[arbitrary(fixed)]`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed<T>(pub T);
+
+impl<T: Clone + Send + 'static> Arbitrary for Fixed<T> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Fixed<T> { Fixed(base_value(g)) }
+    fn shrink_count(&self) -> Option<usize> { Some(0) }
+}
+
+impl<T: 'static> Shrink for Fixed<T> {}
+
+/// A wrapper that is generated like `A` but never shrunk.
+///
+/// Unlike `Fixed`, the value is still random. This is useful for a field
+/// whose shrinks would take too long to search, such as a large
+/// configuration, while the rest of the input is shrunk as usual.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NoShrink<A>(pub A);
+
+impl<A: Arbitrary> Arbitrary for NoShrink<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> NoShrink<A> {
+        NoShrink(Arbitrary::arbitrary(g))
+    }
+    fn shrink_count(&self) -> Option<usize> { Some(0) }
+}
+
+impl<A: 'static> Shrink for NoShrink<A> {}
+
+#[cfg(feature = "semver")]
+impl Arbitrary for Version {
+    fn arbitrary<G: Gen>(g: &mut G) -> Version {
+        let mut v = Version {
+            major: Arbitrary::arbitrary(g),
+            minor: Arbitrary::arbitrary(g),
+            patch: Arbitrary::arbitrary(g),
+            pre: vec![],
+            build: vec![],
+        };
+        if g.gen_weighted_bool(4) {
+            v.pre = semver_identifiers(g);
+        }
+        if g.gen_weighted_bool(4) {
+            v.build = semver_identifiers(g);
+        }
+        v
+    }
+}
+
+#[cfg(feature = "semver")]
+impl Shrink for Version {
+    fn shrink(&self) -> Box<Iterator<Item=Version>+'static> {
+        // Drop the build metadata and pre-release parts before touching
+        // the version numbers.
+        let mut xs = vec![];
+        if !self.build.is_empty() {
+            let mut v = self.clone();
+            v.build = vec![];
+            xs.push(v);
+        }
+        if !self.pre.is_empty() {
+            let mut v = self.clone();
+            v.pre = vec![];
+            xs.push(v);
+        }
+        let (pre, build) = (self.pre.clone(), self.build.clone());
+        let nums = (self.major, self.minor, self.patch).shrink()
+            .map(move |(major, minor, patch)| Version {
+                major: major,
+                minor: minor,
+                patch: patch,
+                pre: pre.clone(),
+                build: build.clone(),
+            });
+        Box::new(xs.into_iter().chain(nums))
+    }
+}
+
+/// Returns a non-empty list of dot separated identifiers that are valid in
+/// both the pre-release and build metadata parts of a version.
+///
+/// Numeric identifiers never have leading zeros (so `0` is never generated)
+/// and alphanumeric identifiers always start with a letter, which guarantees
+/// that every identifier parses back to itself.
+#[cfg(feature = "semver")]
+fn semver_identifiers<G: Gen>(g: &mut G) -> Vec<Identifier> {
+    const ALNUM: &'static [u8] =
+        b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    let n = g.gen_range(1, 4);
+    (0..n).map(|_| {
+        if g.gen() {
+            let s = g.size();
+            Identifier::Numeric(g.gen_range(1, s as u64 + 2))
+        } else {
+            let len = g.gen_range(0, 8);
+            let mut id = String::new();
+            id.push(ALNUM[g.gen_range(10, ALNUM.len())] as char);
+            for _ in 0..len {
+                id.push(ALNUM[g.gen_range(0, ALNUM.len())] as char);
+            }
+            Identifier::AlphaNumeric(id)
+        }
+    }).collect()
+}
+
+/// Returns a random length for a collection, at least `g.min_size()` and
+/// less than `g.size()` unless the size is at most the minimum size, in
+/// which case it is always the minimum size.
+pub fn gen_len<G: Gen>(g: &mut G) -> usize {
+    match (g.min_size(), g.size()) {
+        (0, 0) => 0,
+        (min, s) => g.gen_range(min, cmp::max(s, min + 1)),
+    }
+}
+
+/// Returns shorter versions of `xs`, starting with the empty vector and
+/// followed by `xs` with contiguous runs of elements of decreasing length
+/// removed.
+///
+/// This is used by generators in this crate that need to shrink a vector
+/// without shrinking its elements.
+pub fn vec_removals<A: Clone>(xs: &[A]) -> Vec<Vec<A>> {
+    Removals::new(xs.to_vec()).collect()
+}
+
+/// Lazily yields the vectors returned by `vec_removals`.
+///
+/// For each run length `k`, from half the length of the vector down to
+/// one, every run of `k` elements that starts at a multiple of `k` is
+/// removed in turn. Since `k` is never the whole length, the empty vector
+/// only comes up once, and each removal leaves a different set of
+/// positions, so no two candidates are the same unless `xs` has repeated
+/// elements.
+struct Removals<A> {
+    xs: Vec<A>,
+    /// Whether the empty vector is still to be yielded.
+    empty: bool,
+    /// The length of the runs being removed, or zero once done.
+    k: usize,
+    /// The start of the next run to remove.
+    i: usize,
+}
+
+impl<A: Clone> Removals<A> {
+    fn new(xs: Vec<A>) -> Removals<A> {
+        let (empty, k) = (xs.len() > 0, xs.len() / 2);
+        Removals { xs: xs, empty: empty, k: k, i: 0 }
+    }
+}
+
+impl<A: Clone> Iterator for Removals<A> {
+    type Item = Vec<A>;
+    fn next(&mut self) -> Option<Vec<A>> {
+        if self.empty {
+            self.empty = false;
+            return Some(vec![]);
+        }
+        while self.k > 0 {
+            let (i, k) = (self.i, self.k);
+            if i + k <= self.xs.len() {
+                self.i += k;
+                let mut removed = self.xs[..i].to_vec();
+                removed.extend(self.xs[i + k..].iter().cloned());
+                return Some(removed);
+            }
+            self.k = k / 2;
+            self.i = 0;
+        }
+        None
+    }
+}
+
+/// Returns the lengths of the prefixes of a vector of length `n` that are
+/// tried right after the empty vector, so that shrinking a long vector
+/// gets it short in a few steps.
+///
+/// Prefixes that removing a run of elements also produces are left out.
+fn short_prefixes(n: usize) -> Vec<usize> {
+    let removes_suffix = |m: usize| {
+        let k = n - m;
+        let mut j = n / 2;
+        while j > k {
+            j = j / 2;
+        }
+        j == k && m % k == 0
+    };
+    let mut lens = vec![];
+    for &m in &[1, 2, n / 4] {
+        if m > 0 && m < n && !lens.contains(&m) && !removes_suffix(m) {
+            lens.push(m);
+        }
+    }
+    lens
+}
+
+/// Lazily shrinks a vector: first by trying the empty vector and a few
+/// short prefixes, then by removing elements, and then by shrinking each
+/// element in turn, one candidate vector per shrunk value.
+struct VecShrinker<A: Shrink + Clone> {
+    removals: Removals<A>,
+    prefixes: ::std::vec::IntoIter<usize>,
+    /// The index of the next element to shrink.
+    elem: usize,
+    /// The shrinker of the element before `elem`, once elements are being
+    /// shrunk.
+    shrinker: Option<Box<Iterator<Item=A>+'static>>,
+}
+
+impl<A: Shrink + Clone> VecShrinker<A> {
+    fn new(xs: Vec<A>) -> VecShrinker<A> {
+        VecShrinker {
+            prefixes: short_prefixes(xs.len()).into_iter(),
+            removals: Removals::new(xs),
+            elem: 0,
+            shrinker: None,
+        }
+    }
+}
+
+impl<A: Shrink + Clone> Iterator for VecShrinker<A> {
+    type Item = Vec<A>;
+    fn next(&mut self) -> Option<Vec<A>> {
+        // The empty vector comes first.
+        if self.removals.empty {
+            return self.removals.next();
+        }
+        if let Some(m) = self.prefixes.next() {
+            return Some(self.removals.xs[..m].to_vec());
+        }
+        if let Some(xs) = self.removals.next() {
+            return Some(xs);
+        }
+        let xs = &self.removals.xs;
+        loop {
+            if let Some(ref mut shrinker) = self.shrinker {
+                if let Some(x) = shrinker.next() {
+                    let mut change_one = xs.clone();
+                    change_one[self.elem - 1] = x;
+                    return Some(change_one);
+                }
+            }
+            if self.elem == xs.len() {
+                return None;
+            }
+            self.shrinker = Some(xs[self.elem].shrink());
+            self.elem += 1;
+        }
+    }
+}
+
+macro_rules! unsigned_shrinker {
+    ($ty:ty) => {
+        mod shrinker {
+            pub struct UnsignedShrinker {
+                x: $ty,
+                i: $ty,
+            }
+
+            impl UnsignedShrinker {
+                pub fn new(x: $ty) -> Box<Iterator<Item=$ty>+'static> {
+                    if x == 0 {
+                        super::empty_shrinker()
+                    } else {
+                        Box::new(vec![0].into_iter().chain(
+                            UnsignedShrinker {
+                                x: x,
+                                i: x / 2,
+                            }
+                        ))
+                    }
+                }
+            }
+
+            impl Iterator for UnsignedShrinker {
+                type Item = $ty;
+                fn next(&mut self) -> Option<$ty> {
+                    if self.x - self.i < self.x {
+                        let result = Some(self.x - self.i);
+                        self.i = self.i / 2;
+                        result
+                    } else {
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+macro_rules! unsigned_arbitrary {
+    ($($ty:ty),*) => {
+        $(
+            impl Arbitrary for $ty {
+                fn arbitrary<G: Gen>(g: &mut G) -> $ty {
+                    #![allow(trivial_numeric_casts)]
+                    let s = g.size();
+                    if g.corner_values() && g.gen_weighted_bool(10) {
+                        let max = <$ty>::max_value();
+                        return *g.choose(&[0, 1, s as $ty, max]).unwrap();
+                    }
+                    match s {
+                        0 => 0,
+                        s => g.gen_range(0, s as $ty),
+                    }
+                }
+                fn shrink_count(&self) -> Option<usize> {
+                    // Zero followed by at most one value per bit.
+                    Some(mem::size_of::<$ty>() * 8 + 1)
+                }
+            }
+
+            impl Shrink for $ty {
+                fn shrink(&self) -> Box<Iterator<Item=$ty>+'static> {
+                    unsigned_shrinker!($ty);
+                    shrinker::UnsignedShrinker::new(*self)
+                }
+            }
+        )*
+    }
+}
+
+unsigned_arbitrary! {
+    usize, u8, u16, u32, u64
+}
+
+macro_rules! signed_shrinker {
+    ($ty:ty) => {
+        mod shrinker {
+            pub struct SignedShrinker {
+                x: $ty,
+                i: $ty,
+            }
+
+            impl SignedShrinker {
+                pub fn new(x: $ty) -> Box<Iterator<Item=$ty>+'static> {
+                    if x == 0 {
+                        super::empty_shrinker()
+                    } else if x == <$ty>::min_value() {
+                        // The minimum has no absolute value, so shrink
+                        // through `MIN + 1` instead.
+                        let x = x + 1;
+                        let shrinker = SignedShrinker { x: x, i: x / 2 };
+                        let items = vec![0, <$ty>::max_value(), x];
+                        Box::new(items.into_iter().chain(shrinker))
+                    } else {
+                        let shrinker = SignedShrinker {
+                            x: x,
+                            i: x / 2,
+                        };
+                        let mut items = vec![0];
+                        if shrinker.i < 0 {
+                            items.push(shrinker.x.abs());
+                        }
+                        Box::new(items.into_iter().chain(shrinker))
+                    }
+                }
+            }
+
+            impl Iterator for SignedShrinker {
+                type Item = $ty;
+                fn next(&mut self) -> Option<$ty> {
+                    if (self.x - self.i).abs() < self.x.abs() {
+                        let result = Some(self.x - self.i);
+                        self.i = self.i / 2;
+                        result
+                    } else {
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+macro_rules! signed_arbitrary {
+    ($($ty:ty),*) => {
+        $(
+            impl Arbitrary for $ty {
+                fn arbitrary<G: Gen>(g: &mut G) -> $ty {
+                    let s = g.size();
+                    if g.corner_values() && g.gen_weighted_bool(10) {
+                        let (min, max) = (<$ty>::min_value(),
+                                          <$ty>::max_value());
+                        return *g.choose(&[0, 1, -1, s as $ty, -(s as $ty),
+                                           max, min]).unwrap();
+                    }
+                    match s {
+                        0 => 0 as $ty,
+                        s => g.gen_range(-(s as $ty), s as $ty),
+                    }
+                }
+                fn shrink_count(&self) -> Option<usize> {
+                    // Zero, the absolute value and at most one value per bit.
+                    Some(mem::size_of::<$ty>() * 8 + 2)
+                }
+            }
+
+            impl Shrink for $ty {
+                fn shrink(&self) -> Box<Iterator<Item=$ty>+'static> {
+                    signed_shrinker!($ty);
+                    shrinker::SignedShrinker::new(*self)
+                }
+            }
+        )*
+    }
+}
+
+signed_arbitrary! {
+    isize, i8, i16, i32, i64
+}
+
+/// Shrinks an unsigned integer the way the `Arbitrary` impls for unsigned
+/// integers do: to zero first, and then ever closer to `x`.
+///
+/// This is useful for types that aren't integers but are shrunk like one,
+/// e.g., an index into a table.
+pub fn shrink_unsigned(x: u64) -> Box<Iterator<Item=u64>+'static> {
+    unsigned_shrinker!(u64);
+    shrinker::UnsignedShrinker::new(x)
+}
+
+/// Shrinks a signed integer the way the `Arbitrary` impls for signed
+/// integers do: to zero first, then to `-x` if `x` is negative, and then
+/// ever closer to `x`.
+pub fn shrink_signed(x: i64) -> Box<Iterator<Item=i64>+'static> {
+    signed_shrinker!(i64);
+    shrinker::SignedShrinker::new(x)
+}
+
+/// Shrinks an integer toward `target` instead of toward zero: to `target`
+/// first, and then ever closer to `value`, halving the distance to it each
+/// time.
+///
+/// The distance between `value` and `target` must fit in `A`, e.g., this
+/// overflows for `shrink_toward(i8::MAX, -1)`.
+pub fn shrink_toward<A>(value: A, target: A) -> Box<Iterator<Item=A>+'static>
+        where A: Arbitrary + Copy + PartialOrd
+               + Add<Output=A> + Sub<Output=A> {
+    if value > target {
+        Box::new((value - target).shrink().map(move |d| target + d))
+    } else {
+        Box::new((target - value).shrink().map(move |d| target - d))
+    }
+}
+
+macro_rules! float_arbitrary {
+    ($($ty:ident, $int:ident);*) => {
+        $(
+            impl Arbitrary for $ty {
+                fn arbitrary<G: Gen>(g: &mut G) -> $ty {
+                    match g.size() {
+                        0 => 0 as $ty,
+                        s => g.gen_range(-(s as $ty), s as $ty),
+                    }
+                }
+                fn shrink_count(&self) -> Option<usize> {
+                    // Counts the candidates `shrink` pushes, some of which
+                    // may turn out to be duplicates.
+                    let x = *self;
+                    if x == 0.0 || x.is_nan() {
+                        let negative = x.is_sign_negative() && !x.is_nan();
+                        return Some(if negative { 1 } else { 0 });
+                    }
+                    // Zero and the absolute value.
+                    let mut n = if x < 0.0 { 2 } else { 1 };
+                    if x.is_finite() {
+                        // The truncated value and the first halving.
+                        n += 2;
+                        let mut half = (x / 2.0).abs();
+                        while half >= 1.0 {
+                            half /= 2.0;
+                            n += 1;
+                        }
+                        n += try_opt!((x as $int).shrink_count());
+                    }
+                    Some(n)
+                }
+            }
+
+            impl Shrink for $ty {
+                fn shrink(&self) -> Box<Iterator<Item=$ty>+'static> {
+                    signed_shrinker!($int);
+                    let x = *self;
+                    if x == 0.0 || x.is_nan() {
+                        // Only `-0.0` can be simplified, by dropping its
+                        // sign.
+                        return if x.is_sign_negative() && !x.is_nan() {
+                            single_shrinker(0.0)
+                        } else {
+                            empty_shrinker()
+                        };
+                    }
+                    let mut xs: Vec<$ty> = vec![0.0];
+                    {
+                        let mut push = |y: $ty| {
+                            if y != x && !xs.contains(&y) {
+                                xs.push(y);
+                            }
+                        };
+                        // Dropping the sign is always a simplification.
+                        if x < 0.0 {
+                            push(-x);
+                        }
+                        if x.is_finite() {
+                            // Then drop the fractional part, and then try
+                            // halving the distance to zero, until it's less
+                            // than one.
+                            push(x.trunc());
+                            let mut half = x / 2.0;
+                            while half != 0.0 {
+                                push(half);
+                                if half.abs() < 1.0 {
+                                    break;
+                                }
+                                half /= 2.0;
+                            }
+                            // Finally, shrink it like an integer.
+                            for i in shrinker::SignedShrinker::new(x as $int) {
+                                push(i as $ty);
+                            }
+                        }
+                    }
+                    Box::new(xs.into_iter())
+                }
+            }
+        )*
+    }
+}
+
+float_arbitrary! {
+    f32, i32;
+    f64, i64
+}
+
+impl Arbitrary for Duration {
+    fn arbitrary<G: Gen>(g: &mut G) -> Duration {
+        let secs: u64 = Arbitrary::arbitrary(g);
+        // At the default size, any number of nanoseconds can come up.
+        let nanos = match g.size() {
+            0 => 0,
+            s => g.gen_range(0, cmp::min(s as u64 * 10_000_000,
+                                         1_000_000_000)) as u32,
+        };
+        Duration::new(secs, nanos)
+    }
+}
+
+impl Shrink for Duration {
+    fn shrink(&self) -> Box<Iterator<Item=Duration>+'static> {
+        let parts = (self.as_secs(), self.subsec_nanos());
+        Box::new(parts.shrink().map(|(s, ns)| Duration::new(s, ns)))
+    }
+}
+
+/// Generated as `UNIX_EPOCH` plus an arbitrary `Duration`, and shrunk
+/// toward `UNIX_EPOCH`.
+impl Arbitrary for SystemTime {
+    fn arbitrary<G: Gen>(g: &mut G) -> SystemTime {
+        let d: Duration = Arbitrary::arbitrary(g);
+        UNIX_EPOCH + d
+    }
+}
+
+impl Shrink for SystemTime {
+    fn shrink(&self) -> Box<Iterator<Item=SystemTime>+'static> {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(d) => Box::new(d.shrink().map(|d| UNIX_EPOCH + d)),
+            Err(e) => Box::new(e.duration().shrink().map(|d| UNIX_EPOCH - d)),
+        }
+    }
+}
+
+impl Arbitrary for Ipv4Addr {
+    fn arbitrary<G: Gen>(g: &mut G) -> Ipv4Addr {
+        let o: [u8; 4] = Arbitrary::arbitrary(g);
+        Ipv4Addr::new(o[0], o[1], o[2], o[3])
+    }
+}
+
+impl Shrink for Ipv4Addr {
+    fn shrink(&self) -> Box<Iterator<Item=Ipv4Addr>+'static> {
+        Box::new(self.octets().shrink().map(|o| {
+            Ipv4Addr::new(o[0], o[1], o[2], o[3])
+        }))
+    }
+}
+
+impl Arbitrary for Ipv6Addr {
+    fn arbitrary<G: Gen>(g: &mut G) -> Ipv6Addr {
+        let s: [u16; 8] = Arbitrary::arbitrary(g);
+        Ipv6Addr::new(s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7])
+    }
+}
+
+impl Shrink for Ipv6Addr {
+    fn shrink(&self) -> Box<Iterator<Item=Ipv6Addr>+'static> {
+        Box::new(self.segments().shrink().map(|s| {
+            Ipv6Addr::new(s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7])
+        }))
+    }
+}
+
+impl Arbitrary for IpAddr {
+    fn arbitrary<G: Gen>(g: &mut G) -> IpAddr {
+        if g.gen() {
+            IpAddr::V4(Arbitrary::arbitrary(g))
+        } else {
+            IpAddr::V6(Arbitrary::arbitrary(g))
+        }
+    }
+}
+
+impl Shrink for IpAddr {
+    fn shrink(&self) -> Box<Iterator<Item=IpAddr>+'static> {
+        match *self {
+            IpAddr::V4(ref a) => Box::new(a.shrink().map(IpAddr::V4)),
+            IpAddr::V6(ref a) => Box::new(a.shrink().map(IpAddr::V6)),
+        }
+    }
+}
+
+impl Arbitrary for SocketAddrV4 {
+    fn arbitrary<G: Gen>(g: &mut G) -> SocketAddrV4 {
+        SocketAddrV4::new(Arbitrary::arbitrary(g), Arbitrary::arbitrary(g))
+    }
+}
+
+impl Shrink for SocketAddrV4 {
+    fn shrink(&self) -> Box<Iterator<Item=SocketAddrV4>+'static> {
+        Box::new((*self.ip(), self.port()).shrink().map(|(ip, port)| {
+            SocketAddrV4::new(ip, port)
+        }))
+    }
+}
+
+/// The flow information and scope ID are always zero.
+impl Arbitrary for SocketAddrV6 {
+    fn arbitrary<G: Gen>(g: &mut G) -> SocketAddrV6 {
+        let (ip, port) = Arbitrary::arbitrary(g);
+        SocketAddrV6::new(ip, port, 0, 0)
+    }
+}
+
+impl Shrink for SocketAddrV6 {
+    fn shrink(&self) -> Box<Iterator<Item=SocketAddrV6>+'static> {
+        Box::new((*self.ip(), self.port()).shrink().map(|(ip, port)| {
+            SocketAddrV6::new(ip, port, 0, 0)
+        }))
+    }
+}
+
+impl Arbitrary for SocketAddr {
+    fn arbitrary<G: Gen>(g: &mut G) -> SocketAddr {
+        if g.gen() {
+            SocketAddr::V4(Arbitrary::arbitrary(g))
+        } else {
+            SocketAddr::V6(Arbitrary::arbitrary(g))
+        }
+    }
+}
+
+impl Shrink for SocketAddr {
+    fn shrink(&self) -> Box<Iterator<Item=SocketAddr>+'static> {
+        match *self {
+            SocketAddr::V4(ref a) => Box::new(a.shrink().map(SocketAddr::V4)),
+            SocketAddr::V6(ref a) => Box::new(a.shrink().map(SocketAddr::V6)),
+        }
+    }
+}
+
+/// An `f64` that is a special value one time in four: NaN, an infinity, a
+/// zero of either sign, a subnormal number or the smallest or largest
+/// finite value.
+///
+/// Otherwise, it is generated like any other `f64`, which is always finite.
+///
+/// Shrinking turns NaN into zero and an infinity into zero or the largest
+/// finite value with the same sign, which then shrinks like any other
+/// `f64`.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct ExtremeFloat(pub f64);
+
+/// The `f32` version of `ExtremeFloat`.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub struct ExtremeFloat32(pub f32);
+
+macro_rules! extreme_float {
+    ($($name:ident, $ty:ident);*) => {
+        $(
+            impl Arbitrary for $name {
+                fn arbitrary<G: Gen>(g: &mut G) -> $name {
+                    if !g.gen_weighted_bool(4) {
+                        return $name(Arbitrary::arbitrary(g));
+                    }
+                    let subnormal = $ty::MIN_POSITIVE / 2.0;
+                    $name(*g.choose(&[
+                        $ty::NAN, $ty::INFINITY, $ty::NEG_INFINITY,
+                        0.0, -0.0, subnormal, -subnormal,
+                        $ty::MIN_POSITIVE, $ty::MAX, $ty::MIN,
+                    ]).unwrap())
+                }
+            }
+
+            impl Shrink for $name {
+                fn shrink(&self) -> Box<Iterator<Item=$name>+'static> {
+                    let x = self.0;
+                    if x.is_nan() {
+                        return single_shrinker($name(0.0));
+                    }
+                    if x.is_infinite() {
+                        let max = if x > 0.0 { $ty::MAX } else { $ty::MIN };
+                        let mut xs = vec![$name(0.0), $name(max)];
+                        if x < 0.0 {
+                            xs.insert(1, $name(-x));
+                        }
+                        return Box::new(xs.into_iter());
+                    }
+                    Box::new(x.shrink().map($name))
+                }
+            }
+        )*
+    }
+}
+
+extreme_float! {
+    ExtremeFloat, f64;
+    ExtremeFloat32, f32
+}
+
+/// An integer whose magnitude is drawn from a log-uniform distribution.
+///
+/// The number of bits in the magnitude is picked uniformly, up to the size
+/// of the generator or the width of the type, whichever is smaller. This
+/// means that `1`, `1000` and `1_000_000` are all about as likely to show
+/// up, unlike with the `Arbitrary` impls for integers, which rarely produce
+/// small values once the size is large. Signed values are negated half of
+/// the time.
+///
+/// Shrinking is the same as for the underlying integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogUniform<T>(pub T);
+
+macro_rules! log_uniform {
+    (unsigned: $($ty:ty),*) => {
+        $(
+            impl Arbitrary for LogUniform<$ty> {
+                fn arbitrary<G: Gen>(g: &mut G) -> LogUniform<$ty> {
+                    let bits = mem::size_of::<$ty>() * 8;
+                    LogUniform(log_uniform_magnitude(g, bits) as $ty)
+                }
+                fn shrink_count(&self) -> Option<usize> {
+                    self.0.shrink_count()
+                }
+            }
+
+            impl Shrink for LogUniform<$ty> {
+                fn shrink(&self)
+                         -> Box<Iterator<Item=LogUniform<$ty>>+'static> {
+                    Box::new(self.0.shrink().map(LogUniform))
+                }
+            }
+        )*
+    };
+    (signed: $($ty:ty),*) => {
+        $(
+            impl Arbitrary for LogUniform<$ty> {
+                fn arbitrary<G: Gen>(g: &mut G) -> LogUniform<$ty> {
+                    let bits = mem::size_of::<$ty>() * 8 - 1;
+                    let x = log_uniform_magnitude(g, bits) as $ty;
+                    LogUniform(if g.gen() { -x } else { x })
+                }
+                fn shrink_count(&self) -> Option<usize> {
+                    self.0.shrink_count()
+                }
+            }
+
+            impl Shrink for LogUniform<$ty> {
+                fn shrink(&self)
+                         -> Box<Iterator<Item=LogUniform<$ty>>+'static> {
+                    Box::new(self.0.shrink().map(LogUniform))
+                }
+            }
+        )*
+    };
+}
+
+log_uniform!(unsigned: usize, u8, u16, u32, u64);
+log_uniform!(signed: isize, i8, i16, i32, i64);
+
+/// Returns a value with a uniformly chosen number of bits, which is at most
+/// `bits` and at most the size of `g`.
+fn log_uniform_magnitude<G: Gen>(g: &mut G, bits: usize) -> u64 {
+    let bits = g.gen_range(0, cmp::min(g.size(), bits) + 1);
+    if bits == 0 {
+        return 0;
+    }
+    let lo = 1u64 << (bits - 1);
+    lo | (g.gen::<u64>() & (lo - 1))
+}
+
+// Non-zero integers are generated like the underlying integer, except that
+// zero becomes one, and shrink like it too, skipping zero.
+macro_rules! non_zero {
+    ($($nz:ident($ty:ty)),*) => {
+        $(
+            impl Arbitrary for $nz {
+                fn arbitrary<G: Gen>(g: &mut G) -> $nz {
+                    let x: $ty = Arbitrary::arbitrary(g);
+                    $nz::new(x).unwrap_or_else(|| $nz::new(1).unwrap())
+                }
+                fn shrink_count(&self) -> Option<usize> {
+                    self.get().shrink_count()
+                }
+            }
+
+            impl Shrink for $nz {
+                fn shrink(&self) -> Box<Iterator<Item=$nz>+'static> {
+                    Box::new(self.get().shrink().filter_map($nz::new))
+                }
+            }
+        )*
+    }
+}
+
+non_zero!(NonZeroUsize(usize), NonZeroU8(u8), NonZeroU16(u16),
+          NonZeroU32(u32), NonZeroU64(u64), NonZeroIsize(isize),
+          NonZeroI8(i8), NonZeroI16(i16), NonZeroI32(i32), NonZeroI64(i64));
+
+/// A signed integer greater than zero.
+///
+/// It is generated like the underlying integer, with negative values
+/// negated and zero replaced by one. Shrinking is the same as for the
+/// underlying integer, except that it never goes to zero or below.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Positive<T>(pub T);
+
+/// A signed integer that is zero or greater.
+///
+/// It is generated like the underlying integer, with negative values
+/// negated. Shrinking is the same as for the underlying integer, except
+/// that it never goes below zero.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NonNegative<T>(pub T);
+
+/// A signed integer less than zero.
+///
+/// It is generated like the underlying integer, with positive values
+/// negated and zero replaced by minus one. Shrinking is the same as for
+/// the underlying integer, except that it never goes to zero or above.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Negative<T>(pub T);
+
+macro_rules! signed_half_line {
+    ($($ty:ty),*) => {
+        $(
+            impl Arbitrary for Positive<$ty> {
+                fn arbitrary<G: Gen>(g: &mut G) -> Positive<$ty> {
+                    let x: $ty = Arbitrary::arbitrary(g);
+                    Positive(match x {
+                        0 => 1,
+                        x if x == <$ty>::min_value() => <$ty>::max_value(),
+                        x => x.abs(),
+                    })
+                }
+            }
+
+            impl Shrink for Positive<$ty> {
+                fn shrink(&self)
+                         -> Box<Iterator<Item=Positive<$ty>>+'static> {
+                    Box::new(self.0.shrink().filter(|&x| x > 0)
+                                            .map(Positive))
+                }
+            }
+
+            impl Arbitrary for NonNegative<$ty> {
+                fn arbitrary<G: Gen>(g: &mut G) -> NonNegative<$ty> {
+                    let x: $ty = Arbitrary::arbitrary(g);
+                    NonNegative(match x {
+                        x if x == <$ty>::min_value() => <$ty>::max_value(),
+                        x => x.abs(),
+                    })
+                }
+            }
+
+            impl Shrink for NonNegative<$ty> {
+                fn shrink(&self)
+                         -> Box<Iterator<Item=NonNegative<$ty>>+'static> {
+                    Box::new(self.0.shrink().filter(|&x| x >= 0)
+                                            .map(NonNegative))
+                }
+            }
+
+            impl Arbitrary for Negative<$ty> {
+                fn arbitrary<G: Gen>(g: &mut G) -> Negative<$ty> {
+                    let x: $ty = Arbitrary::arbitrary(g);
+                    Negative(if x == 0 { -1 } else if x > 0 { -x } else { x })
+                }
+            }
+
+            impl Shrink for Negative<$ty> {
+                fn shrink(&self)
+                         -> Box<Iterator<Item=Negative<$ty>>+'static> {
+                    Box::new(self.0.shrink().filter(|&x| x < 0)
+                                            .map(Negative))
+                }
+            }
+        )*
+    }
+}
+
+signed_half_line!(isize, i8, i16, i32, i64);
+
+/// An integer in the inclusive range from `lo()` to `hi()`, which it
+/// keeps when it is shrunk.
+///
+/// The bounds aren't known until runtime, so this doesn't implement
+/// `Arbitrary`. It is meant for the `Arbitrary` impls of types with a field
+/// in a range, which generate it with `generate` and call `shrink` on it
+/// when they are shrunk:
+///
+/// ```rust
+/// use quickcheck::{Bounded, StdGen};
+///
+/// let mut g = StdGen::from_seed(1, 100);
+/// let percent = Bounded::<u8>::generate(&mut g, 0, 100);
+/// assert!(percent.value() <= 100);
+/// assert!(percent.shrink().all(|p| p.value() <= 100));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Bounded<T> {
+    value: T,
+    lo: T,
+    hi: T,
+}
+
+macro_rules! bounded {
+    ($($ty:ident),*) => {
+        $(
+            impl Bounded<$ty> {
+                /// Returns a value picked uniformly from `lo` to `hi`,
+                /// both included.
+                ///
+                /// # Panics
+                ///
+                /// Panics if `lo` is greater than `hi`.
+                pub fn generate<G: Gen>(g: &mut G, lo: $ty, hi: $ty)
+                                       -> Bounded<$ty> {
+                    assert!(lo <= hi, "empty range from {} to {}", lo, hi);
+                    let value = if hi < $ty::MAX {
+                        g.gen_range(lo, hi + 1)
+                    } else if lo > $ty::MIN {
+                        g.gen_range(lo - 1, hi) + 1
+                    } else {
+                        g.gen()
+                    };
+                    Bounded { value: value, lo: lo, hi: hi }
+                }
+
+                /// Returns `value` with the bounds `lo` and `hi`.
+                ///
+                /// # Panics
+                ///
+                /// Panics if `value` is not between `lo` and `hi`.
+                pub fn new(value: $ty, lo: $ty, hi: $ty) -> Bounded<$ty> {
+                    assert!(lo <= value && value <= hi,
+                            "{} is not from {} to {}", value, lo, hi);
+                    Bounded { value: value, lo: lo, hi: hi }
+                }
+
+                pub fn value(&self) -> $ty { self.value }
+                pub fn lo(&self) -> $ty { self.lo }
+                pub fn hi(&self) -> $ty { self.hi }
+
+                /// Shrinks the value toward the value in the range that is
+                /// closest to zero, never leaving the range.
+                pub fn shrink(&self)
+                             -> Box<Iterator<Item=Bounded<$ty>>+'static> {
+                    let Bounded { value, lo, hi } = *self;
+                    let target = cmp::min(cmp::max(0, lo), hi);
+                    let first = if value == target { None }
+                                else { Some(target) };
+                    let rest = value.shrink().filter(move |&x| {
+                        lo <= x && x <= hi && x != target
+                    });
+                    Box::new(first.into_iter().chain(rest).map(move |x| {
+                        Bounded { value: x, lo: lo, hi: hi }
+                    }))
+                }
+            }
+        )*
+    }
+}
+
+bounded!(usize, u8, u16, u32, u64, isize, i8, i16, i32, i64);
+
+/// An integer drawn uniformly from all values of its type, regardless of
+/// the size of the generator.
+///
+/// The `Arbitrary` impls for integers never exceed the size, so they miss
+/// overflow bugs. Shrinking is the same as for the underlying integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FullRange<T>(pub T);
+
+macro_rules! full_range {
+    ($($ty:ty),*) => {
+        $(
+            impl Arbitrary for FullRange<$ty> {
+                fn arbitrary<G: Gen>(g: &mut G) -> FullRange<$ty> {
+                    FullRange(g.gen())
+                }
+                fn shrink_count(&self) -> Option<usize> {
+                    self.0.shrink_count()
+                }
+            }
+
+            impl Shrink for FullRange<$ty> {
+                fn shrink(&self)
+                         -> Box<Iterator<Item=FullRange<$ty>>+'static> {
+                    Box::new(self.0.shrink().map(FullRange))
+                }
+            }
+        )*
+    }
+}
+
+full_range!(usize, u8, u16, u32, u64, isize, i8, i16, i32, i64);
+
+#[cfg(test)]
+mod test {
+    use rand;
+    use rand::Rng;
+    use std::borrow::Cow;
+    use std::cell::{Cell, RefCell};
+    use std::cmp::{Ordering, Reverse, max};
+    use std::collections::{
+        BTreeMap, BTreeSet, BinaryHeap, Bound, HashMap, HashSet, LinkedList,
+        VecDeque,
+    };
+    use std::f32;
+    use std::ffi::{CString, OsString};
+    use std::f64;
+    use std::fmt::Debug;
+    use std::hash::{BuildHasherDefault, Hash, Hasher};
+    use std::i32;
+    use std::i64;
+    use std::i8;
+    use std::marker::PhantomData;
+    use std::net::{
+        IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6,
+    };
+    use std::num::{NonZeroI32, NonZeroI8, NonZeroU32, NonZeroU64, Wrapping};
+    use std::ops::{Range, RangeInclusive};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use std::u32;
+    use std::u64;
+    use std::usize;
+    use super::{
+        Arbitrary, Ascii, AsciiString, Bounded, ExtremeFloat, ExtremeFloat32,
+        Fixed, FullRange, Gen, LogUniform, Negative, NoShrink,
+        NonEmptyVec, NonNegative, NoneBiased, Positive, RawBytes,
+        SomeBiased, Sorted, SplitMix64, StdGen, Unique,
+        choose_variant, empty_domain, empty_shrinker, filter_shrinker,
+        map_shrinker, resize, shrink_signed, shrink_toward, shrink_unsigned,
+        unique_entries, variant_weight,
+    };
+    use shrink::{Shrink, minimize};
+
+    #[cfg(feature = "bytes")]
+    use bytes::{Bytes, BytesMut};
+    #[cfg(feature = "collect_impls")]
+    use collect::TrieMap;
+    #[cfg(feature = "rust_decimal")]
+    use rust_decimal::Decimal;
+    #[cfg(feature = "semver")]
+    use semver::Version;
+
+    // Arbitrary testing. (Not much here. What else can I reasonably test?)
+    #[test]
+    fn arby_unit() {
+        assert_eq!(arby::<()>(), ());
+    }
+
+    #[test]
+    fn arby_int() {
+        rep(&mut || { let n: isize = arby(); assert!(n >= -5 && n <= 5); } );
+    }
+
+    #[test]
+    fn arby_uint() {
+        rep(&mut || { let n: usize = arby(); assert!(n <= 5); } );
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn arby_semver() {
+        rep(&mut || {
+            let v: Version = arby();
+            let s = v.to_string();
+            assert_eq!(Version::parse(&s).unwrap().to_string(), s);
+        });
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        retries: usize,
+        verbose: bool,
+    }
+
+    fn base_config() -> Config {
+        Config { name: "base".to_string(), retries: 3, verbose: true }
+    }
+
+    #[test]
+    fn arby_fixed() {
+        let mut g = gen().with_fixture(base_config());
+        let mut seen = HashSet::new();
+        for _ in 0..100 {
+            let (base, retries): (Fixed<Config>, usize) =
+                Arbitrary::arbitrary(&mut g);
+            let c = Config { retries: retries, ..base.0 };
+            assert_eq!(c.name, "base");
+            assert!(c.verbose);
+            seen.insert(c.retries);
+        }
+        assert!(seen.len() > 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "no base value")]
+    fn arby_fixed_without_base() {
+        let _: Fixed<Config> = arby();
+    }
+
+    #[test]
+    fn arby_biased_options() {
+        let mut g = gen();
+        let somes = (0..1000)
+            .filter(|_| SomeBiased::<bool>::arbitrary(&mut g).0.is_some())
+            .count();
+        let nones = (0..1000)
+            .filter(|_| NoneBiased::<bool>::arbitrary(&mut g).0.is_none())
+            .count();
+        assert!(somes > 800, "only {} of 1000 were Some", somes);
+        assert!(nones > 800, "only {} of 1000 were None", nones);
+    }
+
+    #[test]
+    fn gen_option_edges() {
+        let mut g = gen();
+        for _ in 0..1000 {
+            assert!(super::gen_option::<_, bool>(&mut g, 0.0).is_some());
+            assert!(super::gen_option::<_, bool>(&mut g, 1.0).is_none());
+        }
+    }
+
+    #[test]
+    fn arby_log_uniform() {
+        let mut g = StdGen::new(rand::thread_rng(), 100);
+        let mut digits = HashSet::new();
+        let mut negative = 0;
+        for _ in 0..1000 {
+            let LogUniform(n): LogUniform<i64> = Arbitrary::arbitrary(&mut g);
+            digits.insert(n.abs().to_string().len());
+            if n < 0 {
+                negative += 1;
+            }
+        }
+        for len in [1, 4, 7, 10, 13, 16].iter() {
+            assert!(digits.contains(len), "no {}-digit values", len);
+        }
+        assert!(negative > 300 && negative < 700);
+
+        let mut g = gen();
+        for _ in 0..1000 {
+            let LogUniform(n): LogUniform<u8> = Arbitrary::arbitrary(&mut g);
+            assert!(n < 32);
+        }
+    }
+
+    #[test]
+    fn non_zeros() {
+        let mut g = gen();
+        for _ in 0..1000 {
+            let x: NonZeroU32 = Arbitrary::arbitrary(&mut g);
+            assert!(x.shrink().all(|x| x.get() != 0));
+            let x: NonZeroI8 = Arbitrary::arbitrary(&mut g);
+            assert!(x.shrink().all(|x| x.get() != 0));
+        }
+        let mut g = StdGen::new(rand::thread_rng(), 0);
+        let x: NonZeroU64 = Arbitrary::arbitrary(&mut g);
+        assert_eq!(x.get(), 1);
+
+        let nz = |x| NonZeroU32::new(x).unwrap();
+        let expected: Vec<u32> = 5u32.shrink().filter(|&x| x != 0).collect();
+        eq(nz(5), expected.into_iter().map(nz).collect());
+        let nz = |x| NonZeroI32::new(x).unwrap();
+        eq(nz(-5), vec![nz(5), nz(-3), nz(-4)]);
+        eq(NonZeroU32::new(1).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn half_lines() {
+        let mut g = gen();
+        g.set_corner_values(true);
+        for _ in 0..1000 {
+            let x: Positive<i32> = Arbitrary::arbitrary(&mut g);
+            assert!(x.0 > 0, "{:?}", x);
+            assert!(x.shrink().all(|x| x.0 > 0));
+            let x: NonNegative<i8> = Arbitrary::arbitrary(&mut g);
+            assert!(x.0 >= 0, "{:?}", x);
+            assert!(x.shrink().all(|x| x.0 >= 0));
+            let x: Negative<i64> = Arbitrary::arbitrary(&mut g);
+            assert!(x.0 < 0, "{:?}", x);
+            assert!(x.shrink().all(|x| x.0 < 0));
+        }
+        eq(Positive(5i32), vec![Positive(3), Positive(4)]);
+        eq(Positive(1i32), vec![]);
+        eq(NonNegative(5i32),
+           vec![NonNegative(0), NonNegative(3), NonNegative(4)]);
+        eq(Negative(-5i32), vec![Negative(-3), Negative(-4)]);
+        eq(Negative(-1i32), vec![]);
+    }
+
+    #[test]
+    fn bounded() {
+        let mut g = gen();
+        g.set_corner_values(true);
+        let mut seen = HashSet::new();
+        for _ in 0..1000 {
+            let x = Bounded::<i32>::generate(&mut g, 10, 20);
+            assert!(x.value() >= 10 && x.value() <= 20, "{:?}", x);
+            assert!(x.shrink().all(|y| y.value() >= 10 && y.value() <= 20));
+            seen.insert(x.value());
+        }
+        assert_eq!(seen.len(), 11);
+
+        fn smallest<F>(lo: i32, x: i32, hi: i32, fails: F) -> i32
+                where F: Fn(i32) -> bool {
+            let mut x = Bounded::<i32>::new(x, lo, hi);
+            while let Some(y) = x.shrink().find(|y| fails(y.value())) {
+                x = y;
+            }
+            x.value()
+        }
+        assert_eq!(smallest(10, 17, 20, |_| true), 10);
+        assert_eq!(smallest(-20, -20, -10, |_| true), -10);
+        assert_eq!(smallest(-5, 7, 10, |_| true), 0);
+        assert_eq!(smallest(10, 17, 20, |x| x > 14), 15);
+
+        let x = Bounded::<u8>::generate(&mut g, 255, 255);
+        assert_eq!(x.value(), 255);
+        assert_eq!(x.shrink().count(), 0);
+        Bounded::<i64>::generate(&mut g, i64::MIN, i64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "empty range from 2 to 1")]
+    fn bounded_empty() {
+        Bounded::<u8>::generate(&mut gen(), 2, 1);
+    }
+
+    #[test]
+    fn full_ranges() {
+        let mut g = gen();
+        let mut bytes = HashSet::new();
+        let (mut large, mut small) = (false, false);
+        for _ in 0..10000 {
+            let FullRange(x): FullRange<i8> = Arbitrary::arbitrary(&mut g);
+            bytes.insert(x);
+            let FullRange(x): FullRange<u64> = Arbitrary::arbitrary(&mut g);
+            large = large || x > u64::MAX - u64::MAX / 100;
+            small = small || x < u64::MAX / 100;
+        }
+        assert_eq!(bytes.len(), 256);
+        assert!(large && small);
+
+        assert_eq!(minimize(FullRange(u64::MAX), |_| true), FullRange(0));
+        assert_eq!(minimize(FullRange(i64::MIN), |_| true), FullRange(0));
+        assert_eq!(minimize(FullRange(u64::MAX), |x| x.0 > 1000),
+                   FullRange(1001));
+    }
+
+    #[derive(Clone, Debug)]
+    enum Tree { Leaf, Node(Box<Tree>, Box<Tree>) }
+
+    impl Tree {
+        fn depth(&self) -> usize {
+            match *self {
+                Tree::Leaf => 0,
+                Tree::Node(ref l, ref r) => 1 + max(l.depth(), r.depth()),
+            }
+        }
+    }
+
+    impl Arbitrary for Tree {
+        fn arbitrary<G: Gen>(g: &mut G) -> Tree {
+            let depth = g.depth();
+            if depth == 0 || g.gen_weighted_bool(4) {
+                return Tree::Leaf;
+            }
+            g.with_depth(depth - 1, |g| {
+                Tree::Node(Box::new(Arbitrary::arbitrary(g)),
+                           Box::new(Arbitrary::arbitrary(g)))
+            })
+        }
+    }
+
+    impl Shrink for Tree {}
+
+    /// A tree that only stops growing when `recurse` says so.
+    #[derive(Clone, Debug)]
+    struct Bush(Vec<Bush>);
+
+    impl Bush {
+        fn depth(&self) -> usize {
+            self.0.iter().map(|b| b.depth() + 1).max().unwrap_or(0)
+        }
+    }
+
+    impl Arbitrary for Bush {
+        fn arbitrary<G: Gen>(g: &mut G) -> Bush {
+            g.recurse(|g| {
+                let n = g.gen_range(1, 3);
+                Bush((0..n).map(|_| Arbitrary::arbitrary(g)).collect())
+            }).unwrap_or(Bush(vec![]))
+        }
+    }
+
+    impl Shrink for Bush {}
+
+    #[derive(Clone, Debug)]
+    enum Void {}
+
+    impl Arbitrary for Void {
+        fn arbitrary<G: Gen>(_: &mut G) -> Void { empty_domain("Void") }
+    }
+
+    impl Shrink for Void {}
+
+    #[test]
+    #[should_panic(expected = "value of type `Void` because it has no values")]
+    fn empty_domains() {
+        arby::<Void>();
+    }
+
+    #[test]
+    fn variant_weights() {
+        let mut g = gen();
+        let weights: Vec<f64> =
+            [0, 1, 3].iter().map(|&n| variant_weight(n)).collect();
+        let mut counts = [0; 3];
+        for _ in 0..10000 {
+            counts[choose_variant(&mut g, &weights)] += 1;
+        }
+        // Expected: 1/1.75, 0.5/1.75 and 0.25/1.75 of the draws.
+        assert!(counts[0] > counts[1] && counts[1] > counts[2],
+                "{:?}", counts);
+        assert!(counts[0] > 5000 && counts[2] > 1000, "{:?}", counts);
+
+        for _ in 0..100 {
+            assert_eq!(choose_variant(&mut g, &[0.0, 2.0, 0.0]), 1);
+        }
+    }
+
+    #[test]
+    fn choose() {
+        let mut g = gen();
+        let empty: [u8; 0] = [];
+        assert_eq!(g.choose(&empty), None);
+        let mut seen = HashSet::new();
+        for _ in 0..1000 {
+            seen.insert(*g.choose(&['a', 'b', 'c']).unwrap());
+        }
+        assert_eq!(seen.len(), 3);
+    }
+
+    #[test]
+    fn choose_weighted() {
+        let mut g = gen();
+        let items = [(6, 'a'), (0, 'b'), (3, 'c'), (1, 'd')];
+        let mut counts = HashMap::new();
+        for _ in 0..10000 {
+            *counts.entry(*g.choose_weighted(&items)).or_insert(0) += 1;
+        }
+        // Expected: 6000, 0, 3000 and 1000 of the draws.
+        assert!(!counts.contains_key(&'b'), "{:?}", counts);
+        for &(w, c) in &[(6000, 'a'), (3000, 'c'), (1000, 'd')] {
+            let n = counts[&c];
+            assert!(n > w * 8 / 10 && n < w * 12 / 10, "{:?}", counts);
+        }
+        assert_eq!(*g.choose_weighted(&[(0, 1), (u32::MAX, 2)]), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot choose from an empty slice")]
+    fn choose_weighted_empty() {
+        let items: [(u32, ()); 0] = [];
+        gen().choose_weighted(&items);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot choose when all weights are zero")]
+    fn choose_weighted_zero() {
+        gen().choose_weighted(&[(0, ()), (0, ())]);
+    }
+
+    #[test]
+    fn min_sizes() {
+        let mut g = StdGen::from_seed(5, 20).with_min_size(10);
+        for _ in 0..100 {
+            let xs: Vec<i32> = Arbitrary::arbitrary(&mut g);
+            assert!(10 <= xs.len() && xs.len() < 20, "{:?}", xs);
+            let s: String = Arbitrary::arbitrary(&mut g);
+            assert!(s.len() >= 10);
+            let xs: NonEmptyVec<i32> = Arbitrary::arbitrary(&mut g);
+            assert!(10 <= xs.0.len() && xs.0.len() < 20, "{:?}", xs);
+            let set: BTreeSet<u8> = Arbitrary::arbitrary(&mut g);
+            assert!(set.len() >= 10, "{:?}", set);
+            let map: HashMap<u8, ()> = Arbitrary::arbitrary(&mut g);
+            assert!(map.len() >= 10, "{:?}", map);
+        }
+        g.set_size(5);
+        let xs: Vec<i32> = Arbitrary::arbitrary(&mut g);
+        assert_eq!(xs.len(), 10);
+        let xs: NonEmptyVec<i32> = Arbitrary::arbitrary(&mut g);
+        assert_eq!(xs.0.len(), 10);
+        let set: HashSet<bool> = Arbitrary::arbitrary(&mut g);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn gen_bytes() {
+        let mut g = StdGen::from_seed(11, 100);
+        let bytes = g.gen_bytes(1024);
+        assert_eq!(bytes.len(), 1024);
+        assert!(bytes.iter().any(|&b| b >= 100));
+        assert_eq!(g.gen_bytes(0), vec![]);
+
+        let mut g = StdGen::from_seed(11, 10).with_min_size(5);
+        for _ in 0..20 {
+            let RawBytes(bs) = Arbitrary::arbitrary(&mut g);
+            assert!(5 <= bs.len() && bs.len() < 10);
+        }
+        eq(RawBytes(vec![]), vec![]);
+        eq(RawBytes(vec![1]), vec![RawBytes(vec![]), RawBytes(vec![0])]);
+    }
+
+    #[test]
+    fn gen_bools() {
+        let mut g = StdGen::from_seed(7, 100);
+        assert!((0..100).all(|_| !g.gen_bool(0.0) && !g.gen_bool(-1.0)));
+        assert!((0..100).all(|_| g.gen_bool(1.0) && g.gen_bool(2.0)));
+        let n = (0..10000).filter(|_| g.gen_bool(0.25)).count();
+        assert!(2000 < n && n < 3000, "{} of 10000 were true", n);
+    }
+
+    #[test]
+    fn split_mix_64_is_stable() {
+        // These must never change, or saved seeds stop reproducing
+        // failures.
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xe220a8397b1dcdaf);
+        assert_eq!(rng.next_u64(), 0x6e789e6aa1b965f4);
+        assert_eq!(rng.next_u64(), 0x06c45d188009454f);
+        assert_eq!(rng.next_u32(), 0xf88bb8a8);
+
+        let mut g = StdGen::from_seed(0x0123456789abcdef, 100);
+        assert_eq!(g.next_u64(), 0x157a3807a48faa9d);
+        assert_eq!(g.next_u64(), 0xd573529b34a1d093);
+    }
+
+    /// A generator that only counts, and doesn't depend on `rand` at all.
+    struct Counter(u32);
+
+    impl Gen for Counter {
+        fn next_u32(&mut self) -> u32 {
+            self.0 = self.0.wrapping_add(0x9e3779b9);
+            self.0
+        }
+        fn size(&self) -> usize { 20 }
+    }
+
+    #[test]
+    fn custom_gens() {
+        fn values(start: u32) -> Vec<(i32, bool, char, String, f64)> {
+            let mut g = Counter(start);
+            (0..10).map(|_| Arbitrary::arbitrary(&mut g)).collect()
+        }
+        let xs = values(0);
+        assert_eq!(xs, values(0));
+        assert!(xs.iter().any(|x| x.0 != xs[0].0));
+        assert!(xs.iter().any(|x| x.1) && xs.iter().any(|x| !x.1));
+        assert!(xs.iter().all(|x| x.0.abs() <= 20 && x.3.len() <= 20));
+        assert!(values(1) != xs);
+
+        let mut g = Counter(0);
+        let mut buf = [0u8; 11];
+        g.fill_bytes(&mut buf);
+        assert!(buf.iter().any(|&b| b != 0));
+        assert_eq!(g.choose::<u8>(&[]), None);
+    }
+
+    #[test]
+    fn seeds() {
+        let mut g1 = StdGen::from_seed(1234, 100);
+        assert_eq!(g1.seed(), 1234);
+        let xs: Vec<i32> = (0..100).map(|_| Arbitrary::arbitrary(&mut g1))
+                                    .collect();
+        assert_eq!(g1.seed(), 1234);
+
+        let mut g2 = StdGen::from_seed(g1.seed(), 100);
+        let ys: Vec<i32> = (0..100).map(|_| Arbitrary::arbitrary(&mut g2))
+                                    .collect();
+        assert_eq!(xs, ys);
+    }
+
+    #[test]
+    fn resized() {
+        let mut g = gen();
+        let mut longest = 0;
+        for _ in 0..100 {
+            let xs: Vec<u8> = resize(&mut g, 100, |g| {
+                Arbitrary::arbitrary(g)
+            });
+            longest = max(longest, xs.len());
+            assert_eq!(g.size(), 5);
+        }
+        assert!(longest > 5, "longest was {}", longest);
+    }
+
+    #[test]
+    fn resized_children() {
+        let mut g = StdGen::new(rand::thread_rng(), 20);
+        let (mut full, mut small) = (0, 0);
+        for _ in 0..100 {
+            let xss: Vec<Vec<i32>> = Arbitrary::arbitrary(&mut g);
+            full += xss.iter().fold(0, |n, xs| n + xs.len());
+            let n = xss.len();
+            let xss: Vec<Vec<i32>> = (0..n).map(|_| {
+                resize(&mut g, 4, |g| Arbitrary::arbitrary(g))
+            }).collect();
+            assert!(xss.iter().all(|xs| xs.len() < 4));
+            small += xss.iter().fold(0, |n, xs| n + xs.len());
+            assert_eq!(g.size(), 20);
+        }
+        assert!(small < full, "{} >= {}", small, full);
+    }
+
+    #[test]
+    fn corner_values() {
+        let mut g = StdGen::new(rand::thread_rng(), 100);
+        assert!(!g.corner_values());
+        for _ in 0..10000 {
+            let x: i32 = Arbitrary::arbitrary(&mut g);
+            assert!(x >= -100 && x < 100);
+        }
+
+        g.set_corner_values(true);
+        let (mut unsigned, mut signed) = (HashSet::new(), HashSet::new());
+        for _ in 0..10000 {
+            let x: u64 = Arbitrary::arbitrary(&mut g);
+            unsigned.insert(x);
+            let x: i8 = Arbitrary::arbitrary(&mut g);
+            signed.insert(x);
+        }
+        for x in &[0, 1, 100, u64::MAX] {
+            assert!(unsigned.contains(x), "{} was never generated", x);
+        }
+        for x in &[0, 1, -1, 100, -100, i8::MAX, i8::MIN] {
+            assert!(signed.contains(x), "{} was never generated", x);
+        }
+    }
 
-    // Arbitrary testing. (Not much here. What else can I reasonably test?)
     #[test]
-    fn arby_unit() {
-        assert_eq!(arby::<()>(), ());
+    fn size_zero() {
+        let mut g = StdGen::new(rand::thread_rng(), 0);
+        for _ in 0..100 {
+            let xs: Vec<i32> = Arbitrary::arbitrary(&mut g);
+            assert_eq!(xs, vec![]);
+            let x: u32 = Arbitrary::arbitrary(&mut g);
+            assert_eq!(x, 0);
+            let x: i32 = Arbitrary::arbitrary(&mut g);
+            assert_eq!(x, 0);
+            let x: f64 = Arbitrary::arbitrary(&mut g);
+            assert_eq!(x, 0.0);
+            let s: String = Arbitrary::arbitrary(&mut g);
+            assert_eq!(s, "");
+        }
     }
 
     #[test]
-    fn arby_int() {
-        rep(&mut || { let n: isize = arby(); assert!(n >= -5 && n <= 5); } );
+    fn max_depth() {
+        let mut g = gen();
+        let mut deepest = 0;
+        for _ in 0..1000 {
+            let t: Tree = g.with_depth(3, |g| Arbitrary::arbitrary(g));
+            assert!(t.depth() <= 3, "{:?}", t);
+            deepest = max(deepest, t.depth());
+        }
+        assert_eq!(deepest, 3);
+        assert_eq!(g.depth(), usize::MAX);
     }
 
     #[test]
-    fn arby_uint() {
-        rep(&mut || { let n: usize = arby(); assert!(n <= 5); } );
+    fn recursion() {
+        let mut g = StdGen::new(rand::thread_rng(), 1000);
+        for _ in 0..100 {
+            let b: Bush = Arbitrary::arbitrary(&mut g);
+            // 1000 can be halved ten times before reaching zero.
+            assert_eq!(b.depth(), 10);
+        }
+        assert_eq!((g.size(), g.depth()), (1000, usize::MAX));
+
+        let b: Bush = g.with_depth(3, |g| Arbitrary::arbitrary(g));
+        assert_eq!(b.depth(), 3);
+        g.set_size(0);
+        assert_eq!(g.recurse(|_| ()), None);
     }
 
     fn arby<A: super::Arbitrary>() -> A {
@@ -517,6 +3353,27 @@ mod test {
         eq(true, vec![false]);
     }
 
+    #[test]
+    fn phantom_datas() {
+        let _: PhantomData<u8> = arby();
+        eq(PhantomData::<u8>, vec![]);
+        eq(PhantomData::<str>, vec![]);
+    }
+
+    #[test]
+    fn orderings() {
+        eq(Ordering::Equal, vec![]);
+        eq(Ordering::Less, vec![Ordering::Equal]);
+        eq(Ordering::Greater, vec![Ordering::Equal]);
+    }
+
+    #[test]
+    fn reverses() {
+        let expected = 5i32.shrink().map(Reverse).collect();
+        eq(Reverse(5i32), expected);
+        eq(Reverse(0u8), vec![]);
+    }
+
     #[test]
     fn options() {
         eq(None::<()>, vec![]);
@@ -524,6 +3381,13 @@ mod test {
         eq(Some(true), vec![None, Some(false)]);
     }
 
+    #[test]
+    fn biased_options() {
+        eq(SomeBiased(Some(true)),
+           vec![SomeBiased(None), SomeBiased(Some(false))]);
+        eq(NoneBiased(None::<bool>), vec![]);
+    }
+
     #[test]
     fn results() {
         // Result<A, B> doesn't implement the Hash trait, so these tests
@@ -533,6 +3397,50 @@ mod test {
         ordered_eq(Err::<(), bool>(true), vec![Err(false)]);
     }
 
+    #[test]
+    fn boxes() {
+        eq(Box::new(false), vec![]);
+        eq(Box::new(true), vec![Box::new(false)]);
+        ordered_eq(Box::new(5i32),
+                   5i32.shrink().map(Box::new).collect());
+    }
+
+    #[test]
+    fn cows() {
+        let mut g = StdGen::new(rand::thread_rng(), 100);
+        let xs: Vec<Cow<'static, str>> =
+            (0..20).map(|_| Arbitrary::arbitrary(&mut g)).collect();
+        assert!(xs.iter().any(|x| !x.is_empty()));
+
+        let empty: Cow<'static, str> = Cow::Borrowed("");
+        eq(empty.clone(), vec![]);
+        eq(Cow::Borrowed("a"), vec![empty.clone()]);
+        assert_eq!(minimize(Cow::Owned::<'static, str>("xyz".to_string()),
+                            |_| true),
+                   empty);
+        let v: Cow<'static, [u8]> = Cow::Borrowed(&[1]);
+        eq(v, vec![Cow::Owned(vec![]), Cow::Owned(vec![0])]);
+    }
+
+    #[test]
+    fn cells() {
+        let shrunk: Vec<i32> = Cell::new(5i32).shrink().map(|c| c.get())
+                                              .collect();
+        assert_eq!(shrunk, 5i32.shrink().collect::<Vec<_>>());
+        let shrunk: Vec<i32> = RefCell::new(5i32).shrink()
+                                                 .map(|c| c.into_inner())
+                                                 .collect();
+        assert_eq!(shrunk, 5i32.shrink().collect::<Vec<_>>());
+        assert_eq!(RefCell::new(vec![1u8]).shrink_count(),
+                   vec![1u8].shrink_count());
+    }
+
+    #[test]
+    fn arcs() {
+        eq(Arc::new(false), vec![]);
+        eq(Arc::new(true), vec![Arc::new(false)]);
+    }
+
     #[test]
     fn tuples() {
         eq((false, false), vec![]);
@@ -556,6 +3464,30 @@ mod test {
             vec![(false, true, false, false), (true, false, false, false)]);
     }
 
+    // std doesn't implement `Eq` or `Debug` for tuples with more than
+    // twelve elements, so only the components that can change are compared.
+    #[test]
+    fn tuples13() {
+        let f = false;
+        let zero = (f, f, f, f, f, f, f, f, f, f, f, f, f);
+        assert_eq!(zero.shrink().count(), 0);
+        let x = (true, f, f, f, f, f, f, f, f, f, f, f, true);
+        assert_eq!(x.shrink().map(|t| (t.0, t.12)).collect::<Vec<_>>(),
+                   vec![(false, true), (true, false)]);
+        assert_eq!(x.shrink_count(), Some(2));
+    }
+
+    #[test]
+    fn tuples16() {
+        let f = false;
+        let zero = (f, f, f, f, f, f, f, f, f, f, f, f, f, f, f, f);
+        assert_eq!(zero.shrink().count(), 0);
+        let x = (true, f, f, f, f, f, f, f, f, f, f, f, f, f, f, true);
+        assert_eq!(x.shrink().map(|t| (t.0, t.15)).collect::<Vec<_>>(),
+                   vec![(false, true), (true, false)]);
+        assert_eq!(x.shrink_count(), Some(2));
+    }
+
     #[test]
     fn ints() {
         // TODO: Test overflow?
@@ -592,6 +3524,309 @@ mod test {
         eq(0i64, vec![]);
     }
 
+    #[test]
+    fn wrappings() {
+        eq(Wrapping(5i32), vec![Wrapping(0), Wrapping(3), Wrapping(4)]);
+        eq(Wrapping(-5i32),
+           vec![Wrapping(5), Wrapping(0), Wrapping(-3), Wrapping(-4)]);
+        eq(Wrapping(0i32), vec![]);
+    }
+
+    #[test]
+    fn int_mins() {
+        let shrunk: Vec<i32> = i32::MIN.shrink().collect();
+        assert_eq!(&shrunk[..3], &[0, i32::MAX, i32::MIN + 1]);
+        assert!(shrunk.len() <= i32::MIN.shrink_count().unwrap());
+        assert!(shrunk.iter().all(|&x| x != i32::MIN));
+
+        let shrunk: Vec<i64> = i64::MIN.shrink().collect();
+        assert_eq!(&shrunk[..3], &[0, i64::MAX, i64::MIN + 1]);
+        assert!(shrunk.len() <= i64::MIN.shrink_count().unwrap());
+        assert!(shrunk.iter().all(|&x| x != i64::MIN));
+    }
+
+    #[test]
+    fn durations() {
+        let mut g = StdGen::new(rand::thread_rng(), 1000);
+        for _ in 0..1000 {
+            let d: Duration = Arbitrary::arbitrary(&mut g);
+            assert!(d.subsec_nanos() < 1_000_000_000);
+            assert!(d.shrink().all(|x| x < d));
+        }
+        eq(Duration::from_secs(0), vec![]);
+        eq(Duration::new(2, 3), vec![
+            Duration::new(0, 3), Duration::new(1, 3),
+            Duration::new(2, 0), Duration::new(2, 2),
+        ]);
+    }
+
+    #[test]
+    fn system_times() {
+        let mut g = StdGen::new(rand::thread_rng(), 1000);
+        for _ in 0..100 {
+            let t: SystemTime = Arbitrary::arbitrary(&mut g);
+            assert!(t >= UNIX_EPOCH);
+        }
+        eq(UNIX_EPOCH, vec![]);
+        let secs = |s| UNIX_EPOCH + Duration::from_secs(s);
+        eq(secs(2), vec![secs(0), secs(1)]);
+        let before = UNIX_EPOCH - Duration::from_secs(2);
+        eq(before, vec![UNIX_EPOCH, UNIX_EPOCH - Duration::from_secs(1)]);
+    }
+
+    #[test]
+    fn ip_addrs() {
+        eq(Ipv4Addr::new(0, 0, 0, 0), vec![]);
+        eq(Ipv4Addr::new(0, 2, 0, 1), vec![
+            Ipv4Addr::new(0, 0, 0, 1), Ipv4Addr::new(0, 1, 0, 1),
+            Ipv4Addr::new(0, 2, 0, 0),
+        ]);
+        let zero = Ipv4Addr::new(0, 0, 0, 0);
+        assert_eq!(minimize(Ipv4Addr::new(10, 0, 0, 1), |_| true), zero);
+
+        eq(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0), vec![]);
+        eq(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1),
+           vec![Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0)]);
+
+        eq(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 1)),
+           vec![IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0))]);
+        eq(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+           vec![IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0))]);
+    }
+
+    #[test]
+    fn socket_addrs() {
+        let zero = Ipv4Addr::new(0, 0, 0, 0);
+        eq(SocketAddrV4::new(zero, 0), vec![]);
+        eq(SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 1), 2), vec![
+            SocketAddrV4::new(zero, 2),
+            SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 1), 0),
+            SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 1), 1),
+        ]);
+
+        let mut g = StdGen::new(rand::thread_rng(), 100);
+        for _ in 0..100 {
+            let a: SocketAddrV6 = Arbitrary::arbitrary(&mut g);
+            assert_eq!((a.flowinfo(), a.scope_id()), (0, 0));
+        }
+
+        let a = SocketAddr::V6(SocketAddrV6::new(
+            Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1), 1, 0, 0));
+        for b in a.shrink() {
+            match b {
+                SocketAddr::V6(_) => {}
+                SocketAddr::V4(_) => panic!("{:?} shrank to {:?}", a, b),
+            }
+        }
+        assert_eq!(minimize(a, |_| true), SocketAddr::V6(SocketAddrV6::new(
+            Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0), 0, 0, 0)));
+    }
+
+    #[test]
+    fn path_bufs() {
+        eq(PathBuf::new(), vec![]);
+        eq(PathBuf::from("a").join("b"), vec![
+            PathBuf::new(), PathBuf::from("a"), PathBuf::from("b"),
+            PathBuf::from("a").join("a"),
+        ]);
+        let mut g = gen();
+        for _ in 0..100 {
+            let p: PathBuf = Arbitrary::arbitrary(&mut g);
+            assert!(p.is_relative());
+            assert!(p.iter().count() < 5);
+            assert!(p.shrink().all(|q| q.iter().count() <= p.iter().count()));
+        }
+    }
+
+    #[test]
+    fn os_strings() {
+        eq(OsString::new(), vec![]);
+        eq(OsString::from("a"), vec![OsString::new()]);
+        assert_eq!(minimize(OsString::from("xyz"), |_| true), OsString::new());
+    }
+
+    #[test]
+    fn c_strings() {
+        let c = |bs: &[u8]| CString::new(bs.to_vec()).unwrap();
+        eq(c(b""), vec![]);
+        eq(c(b"\x01"), vec![c(b"")]);
+        eq(c(b"\x03"), vec![c(b""), c(b"\x02")]);
+        assert_eq!(minimize(c(b"abc"), |_| true), c(b""));
+        let mut g = StdGen::new(rand::thread_rng(), 100);
+        for _ in 0..100 {
+            let s: CString = Arbitrary::arbitrary(&mut g);
+            assert!(!s.as_bytes().contains(&0));
+            assert!(s.shrink().all(|t| !t.as_bytes().contains(&0)));
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        eq(0u32..0, vec![]);
+        eq(1u32..2, vec![0..2, 1..1]);
+        eq(2u32..2, vec![0..2, 1..2]);
+        let mut g = gen();
+        for _ in 0..100 {
+            let r: Range<u32> = Arbitrary::arbitrary(&mut g);
+            assert!(r.start <= r.end);
+            assert!(r.shrink().all(|r| r.start <= r.end));
+        }
+    }
+
+    #[test]
+    fn inclusive_ranges() {
+        eq(0u32..=0, vec![]);
+        eq(1u32..=2, vec![0..=2, 1..=1]);
+        eq(2u32..=2, vec![0..=2, 1..=2]);
+        let mut g = gen();
+        for _ in 0..100 {
+            let r: RangeInclusive<u32> = Arbitrary::arbitrary(&mut g);
+            assert!(r.start() <= r.end());
+            assert!(r.shrink().all(|r| r.start() <= r.end()));
+        }
+    }
+
+    #[test]
+    fn bounds() {
+        eq(Bound::Unbounded::<u32>, vec![]);
+        eq(Bound::Included(0u32), vec![Bound::Unbounded]);
+        eq(Bound::Included(2u32),
+           vec![Bound::Unbounded, Bound::Included(0), Bound::Included(1)]);
+        eq(Bound::Excluded(1u32), vec![Bound::Unbounded, Bound::Excluded(0)]);
+        assert_eq!(minimize(Bound::Excluded(9u32), |b| *b != Bound::Unbounded),
+                   Bound::Excluded(0));
+    }
+
+    #[test]
+    fn floats() {
+        let shrunk: Vec<f64> = (-0.0f64).shrink().collect();
+        assert_eq!(shrunk.len(), 1);
+        assert!(shrunk[0] == 0.0 && shrunk[0].is_sign_positive());
+        assert_eq!(0.0f64.shrink().count(), 0);
+
+        assert!((-3.0f64).shrink().any(|x| x == 3.0));
+        assert!((-3.5f64).shrink().any(|x| x == 3.5));
+        assert!((-3.5f32).shrink().any(|x| x == 3.5));
+        assert!(!3.5f64.shrink().any(|x| x < 0.0));
+        assert_eq!((-3.0f64).shrink().filter(|&x| x == 3.0).count(), 1);
+
+        let shrunk: Vec<f64> = 1.5f64.shrink().collect();
+        assert_eq!(shrunk, vec![0.0, 1.0, 0.75]);
+        let shrunk: Vec<f64> = (-6.5f64).shrink().collect();
+        assert_eq!(shrunk, vec![0.0, 6.5, -6.0, -3.25, -1.625, -0.8125,
+                                6.0, -3.0, -5.0]);
+        assert_eq!(3.0f32.shrink().collect::<Vec<_>>(),
+                   vec![0.0, 1.5, 0.75, 2.0]);
+        assert_eq!((-1.0f64 / 0.0).shrink().collect::<Vec<_>>(),
+                   vec![0.0, 1.0 / 0.0]);
+    }
+
+    #[test]
+    fn float_shrink_counts() {
+        assert_eq!(0.0f64.shrink_count(), Some(0));
+        assert_eq!((-0.0f64).shrink_count(), Some(1));
+        assert_eq!(f64::NAN.shrink_count(), Some(0));
+        assert_eq!(f64::INFINITY.shrink_count(), Some(1));
+        assert_eq!(f32::NEG_INFINITY.shrink_count(), Some(2));
+        for &x in &[1.5f64, -6.5, 1e300, -1e-300, f64::MAX, f64::MIN] {
+            assert!(x.shrink().count() <= x.shrink_count().unwrap(), "{}", x);
+        }
+        let mut g = gen();
+        for _ in 0..100 {
+            let x: f32 = Arbitrary::arbitrary(&mut g);
+            assert!(x.shrink().count() <= x.shrink_count().unwrap(), "{}", x);
+        }
+    }
+
+    #[test]
+    fn extreme_floats() {
+        let mut g = gen();
+        let mut seen = [false; 7];
+        for _ in 0..2000 {
+            let ExtremeFloat(x): ExtremeFloat = Arbitrary::arbitrary(&mut g);
+            let i = if x.is_nan() {
+                0
+            } else if x == f64::INFINITY {
+                1
+            } else if x == f64::NEG_INFINITY {
+                2
+            } else if x == 0.0 && x.is_sign_positive() {
+                3
+            } else if x == 0.0 {
+                4
+            } else if x.abs() < f64::MIN_POSITIVE {
+                5
+            } else {
+                6
+            };
+            seen[i] = true;
+        }
+        assert_eq!(seen, [true; 7]);
+
+        for _ in 0..100 {
+            let ExtremeFloat32(x): ExtremeFloat32 = arby();
+            assert!(x.is_nan() || x.is_infinite() || x.abs() <= f32::MAX);
+        }
+    }
+
+    #[test]
+    fn extreme_float_shrinks() {
+        let shrunk: Vec<_> = ExtremeFloat(f64::NAN).shrink().collect();
+        assert_eq!(shrunk, vec![ExtremeFloat(0.0)]);
+        let shrunk: Vec<_> = ExtremeFloat(f64::INFINITY).shrink().collect();
+        assert_eq!(shrunk, vec![ExtremeFloat(0.0), ExtremeFloat(f64::MAX)]);
+        let shrunk: Vec<_> = ExtremeFloat32(f32::NEG_INFINITY).shrink()
+                                                              .collect();
+        assert_eq!(shrunk, vec![ExtremeFloat32(0.0),
+                                ExtremeFloat32(f32::INFINITY),
+                                ExtremeFloat32(f32::MIN)]);
+        assert!(ExtremeFloat(f64::MAX).shrink().all(|x| x.0.is_finite()));
+        assert_eq!(ExtremeFloat(0.0).shrink().count(), 0);
+    }
+
+    #[test]
+    fn shrinker_combinators() {
+        #[derive(Debug, PartialEq)]
+        struct Even(u32);
+        let evens = filter_shrinker(10u32.shrink(), |&x| x % 2 == 0);
+        let evens = map_shrinker(evens, Even);
+        assert_eq!(evens.collect::<Vec<_>>(), vec![Even(0), Even(8)]);
+        assert_eq!(map_shrinker(empty_shrinker::<u32>(), Even).count(), 0);
+        assert_eq!(filter_shrinker(5u32.shrink(), |_| false).count(), 0);
+    }
+
+    #[test]
+    fn shrink_integers() {
+        fn collect<I: Iterator>(xs: I) -> Vec<I::Item> { xs.collect() }
+        assert_eq!(collect(shrink_unsigned(5)), vec![0, 3, 4]);
+        assert_eq!(collect(shrink_unsigned(0)), vec![]);
+        assert_eq!(collect(shrink_signed(5)), vec![0, 3, 4]);
+        assert_eq!(collect(shrink_signed(-5)), vec![0, 5, -3, -4]);
+        assert_eq!(collect(shrink_signed(0)), vec![]);
+        for &x in &[0, 1, 100, u64::MAX] {
+            assert_eq!(collect(shrink_unsigned(x)), collect(x.shrink()));
+        }
+        for &x in &[0, -1, 100, i64::MIN, i64::MAX] {
+            assert_eq!(collect(shrink_signed(x)), collect(x.shrink()));
+        }
+    }
+
+    #[test]
+    fn shrink_towards() {
+        fn collect<I: Iterator>(xs: I) -> Vec<I::Item> { xs.collect() }
+        assert_eq!(collect(shrink_toward(10u32, 4)), vec![4, 7, 9]);
+        assert_eq!(collect(shrink_toward(9u32, 4)), vec![4, 7, 8]);
+        assert_eq!(collect(shrink_toward(-1i32, 4)), vec![4, 1, 0]);
+        assert_eq!(collect(shrink_toward(4u8, 4)), vec![]);
+        assert_eq!(collect(shrink_toward(5i64, 0)),
+                   collect(5i64.shrink()));
+        let mut x = 1000i32;
+        while let Some(y) = shrink_toward(x, 1).find(|&y| y > 100) {
+            x = y;
+        }
+        assert_eq!(x, 101);
+    }
+
     #[test]
     fn uints() {
         eq(5usize, vec![0, 3, 4]);
@@ -635,6 +3870,246 @@ mod test {
         );
     }
 
+    #[test]
+    fn boxed_slices() {
+        let boxed = |v: Vec<isize>| v.into_boxed_slice();
+        eq(boxed(vec![]), vec![]);
+        eq(boxed(vec![1]), vec![boxed(vec![]), boxed(vec![0])]);
+        eq(boxed(vec![3, 5]),
+           vec![boxed(vec![]), boxed(vec![5]), boxed(vec![3]),
+                boxed(vec![0, 5]), boxed(vec![2, 5]),
+                boxed(vec![3, 0]), boxed(vec![3, 3]), boxed(vec![3, 4])]);
+    }
+
+    #[test]
+    fn non_empty_vecs() {
+        for &size in &[0, 1, 2, 5] {
+            let mut g = StdGen::new(rand::thread_rng(), size);
+            for _ in 0..100 {
+                let xs: NonEmptyVec<u8> = Arbitrary::arbitrary(&mut g);
+                assert!(xs.0.len() >= 1 && xs.0.len() < max(size, 2));
+                assert!(xs.shrink().all(|ys| ys.0.len() >= 1));
+            }
+        }
+        eq(NonEmptyVec(vec![0u8]), vec![]);
+        eq(NonEmptyVec(vec![1u8]), vec![NonEmptyVec(vec![0])]);
+        eq(NonEmptyVec(vec![0u8, 0, 1]), vec![
+            NonEmptyVec(vec![0]), NonEmptyVec(vec![0, 1]),
+            NonEmptyVec(vec![0, 0]), NonEmptyVec(vec![0, 0, 0]),
+        ]);
+    }
+
+    #[test]
+    fn sorted_vecs() {
+        let is_sorted = |xs: &[i32]| xs.windows(2).all(|w| w[0] <= w[1]);
+        let mut g = gen();
+        for _ in 0..100 {
+            let xs: Sorted<Vec<i32>> = Arbitrary::arbitrary(&mut g);
+            assert!(is_sorted(&xs), "{:?}", xs);
+            assert!(xs.shrink().all(|ys| is_sorted(&ys)));
+        }
+        eq(Sorted(vec![0, 1]), vec![
+            Sorted(vec![]), Sorted(vec![0]), Sorted(vec![1]),
+            Sorted(vec![0, 0]),
+        ]);
+    }
+
+    #[test]
+    fn unique_vecs() {
+        let is_unique = |xs: &[i32]| {
+            xs.iter().collect::<HashSet<_>>().len() == xs.len()
+        };
+        let mut g = gen();
+        for _ in 0..100 {
+            let xs: Unique<Vec<i32>> = Arbitrary::arbitrary(&mut g);
+            assert!(is_unique(&xs), "{:?}", xs);
+            assert!(xs.shrink().all(|ys| is_unique(&ys)));
+        }
+        eq(Unique(vec![0, 1]), vec![
+            Unique(vec![]), Unique(vec![0]), Unique(vec![1]),
+        ]);
+    }
+
+    #[test]
+    fn vecs_shrink_lazily() {
+        // Computing every candidate up front would take far too long.
+        let xs = vec![1u8; 1000000];
+        let mut shrunk = xs.shrink();
+        assert_eq!(shrunk.next(), Some(vec![]));
+        assert_eq!(shrunk.next().map(|ys| ys.len()), Some(1));
+        assert_eq!(shrunk.nth(2).map(|ys| ys.len()), Some(500000));
+    }
+
+    #[test]
+    fn vecs_shrink_without_repeats() {
+        fn no_repeats(xs: Vec<i32>) {
+            let shrunk: Vec<Vec<i32>> = xs.shrink().collect();
+            let unique: HashSet<Vec<i32>> = shrunk.iter().cloned().collect();
+            assert_eq!(shrunk.len(), unique.len(), "{:?}", shrunk);
+            assert!(!shrunk.contains(&xs));
+        }
+        no_repeats(vec![0]);
+        no_repeats(vec![1]);
+        no_repeats(vec![0, 1]);
+        no_repeats((1..20).collect());
+        assert_eq!(vec![0i32].shrink().collect::<Vec<_>>(), vec![vec![]]);
+    }
+
+    #[test]
+    fn vecs_shrink_to_short_prefixes() {
+        fn steps(min_len: usize) -> usize {
+            let mut xs = vec![1i32; 64];
+            let mut steps = 0;
+            while let Some(ys) = xs.shrink().find(|ys| ys.len() >= min_len) {
+                xs = ys;
+                steps += 1;
+            }
+            assert_eq!(xs, vec![0; min_len]);
+            steps - min_len
+        }
+        // Only removing runs takes 6 steps to get to one element and 5 to
+        // get to three.
+        assert_eq!(steps(1), 1);
+        assert_eq!(steps(3), 3);
+        assert_eq!(super::short_prefixes(64), vec![1, 2, 16]);
+        assert_eq!(super::short_prefixes(3), vec![1]);
+        assert_eq!(super::short_prefixes(2), vec![]);
+    }
+
+    #[test]
+    fn vec_removals() {
+        let xss = super::vec_removals(&[1, 2, 3, 4, 5]);
+        assert_eq!(xss, vec![
+            vec![], vec![3, 4, 5], vec![1, 2, 5],
+            vec![2, 3, 4, 5], vec![1, 3, 4, 5], vec![1, 2, 4, 5],
+            vec![1, 2, 3, 5], vec![1, 2, 3, 4],
+        ]);
+        assert_eq!(super::vec_removals::<u8>(&[]), Vec::<Vec<u8>>::new());
+    }
+
+    #[test]
+    fn arrays() {
+        eq([5i32, 0, 0], vec![[0, 0, 0], [3, 0, 0], [4, 0, 0]]);
+        eq([1u8, 1], vec![[0, 1], [1, 0]]);
+        eq([0u8; 32], vec![]);
+        let empty: [u8; 0] = arby();
+        eq(empty, vec![]);
+    }
+
+    #[test]
+    fn vec_deques() {
+        fn deque(xs: Vec<isize>) -> VecDeque<isize> {
+            xs.into_iter().collect()
+        }
+        eq(deque(vec![]), vec![]);
+        eq(deque(vec![1]), vec![deque(vec![]), deque(vec![0])]);
+        eq(deque(vec![3, 5]),
+           vec![deque(vec![]), deque(vec![5]), deque(vec![3]),
+                deque(vec![0, 5]), deque(vec![2, 5]), deque(vec![3, 0]),
+                deque(vec![3, 3]), deque(vec![3, 4])]);
+    }
+
+    #[test]
+    fn linked_lists() {
+        fn list(xs: Vec<isize>) -> LinkedList<isize> {
+            xs.into_iter().collect()
+        }
+        eq(list(vec![]), vec![]);
+        eq(list(vec![1]), vec![list(vec![]), list(vec![0])]);
+        assert_eq!(minimize(list(vec![3, 5]), |_| true), list(vec![]));
+    }
+
+    #[test]
+    fn binary_heaps() {
+        fn sorted(xs: Box<Iterator<Item=BinaryHeap<isize>>>)
+                 -> Vec<Vec<isize>> {
+            xs.map(|h| h.into_sorted_vec()).collect()
+        }
+        let heap = |xs: Vec<isize>| -> BinaryHeap<isize> {
+            xs.into_iter().collect()
+        };
+        assert_eq!(sorted(heap(vec![]).shrink()), Vec::<Vec<isize>>::new());
+        assert_eq!(sorted(heap(vec![1]).shrink()), vec![vec![], vec![0]]);
+        for h in heap(vec![5, -2, 7]).shrink() {
+            assert_eq!(h.peek(), h.iter().max());
+        }
+    }
+
+    #[test]
+    fn fixeds() {
+        let (fixed, n) = (Fixed(base_config()), 2usize);
+        assert_eq!(fixed.shrink().count(), 0);
+        for (f, _) in (fixed.clone(), n).shrink() {
+            assert_eq!(f, fixed);
+        }
+    }
+
+    #[test]
+    fn no_shrinks() {
+        assert_eq!(NoShrink(vec![1, 2, 3]).shrink().count(), 0);
+        let mut g = StdGen::from_seed(3, 100);
+        let xs: BTreeSet<i32> =
+            (0..20).map(|_| NoShrink::<i32>::arbitrary(&mut g).0).collect();
+        assert!(xs.len() > 1);
+        for (x, _) in (NoShrink(7i32), 2usize).shrink() {
+            assert_eq!(x, NoShrink(7));
+        }
+    }
+
+    #[test]
+    fn vec_shrink_counts() {
+        let empty: Vec<isize> = vec![];
+        assert_eq!(empty.shrink_count(), Some(0));
+        for xs in vec![vec![1isize], vec![11], vec![3, 5], vec![-7, 0, 9, 2]] {
+            let bound = xs.shrink_count().unwrap();
+            assert!(xs.shrink().count() <= bound);
+        }
+        assert_eq!(vec![false, true].shrink_count(), Some(4));
+        assert_eq!(vec![false, true].shrink().count(), 4);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn bytes() {
+        let b = |xs: &[u8]| Bytes::from(xs.to_vec());
+        eq(b(&[]), vec![]);
+        eq(b(&[1]), vec![b(&[]), b(&[0])]);
+        eq(b(&[11]), vec![b(&[]), b(&[0]), b(&[6]), b(&[9]), b(&[10])]);
+        eq(
+            b(&[3, 5]),
+            vec![b(&[]), b(&[5]), b(&[3]), b(&[0,5]), b(&[2,5]),
+                 b(&[3,0]), b(&[3,3]), b(&[3,4])]
+        );
+        // Shrinking a slice of a larger buffer only sees the slice.
+        eq(Bytes::from(vec![7, 1, 7]).slice(1, 2), vec![b(&[]), b(&[0])]);
+
+        let bm = |xs: &[u8]| BytesMut::from(xs.to_vec());
+        ordered_eq(bm(&[1]), vec![bm(&[]), bm(&[0])]);
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn decimals() {
+        let mut g = super::StdGen::new(rand::thread_rng(), 100);
+        for _ in 0..1000 {
+            let d: Decimal = Arbitrary::arbitrary(&mut g);
+            assert!(d.scale() <= 28);
+            for s in d.shrink() {
+                assert!(s.mantissa().abs() <= d.mantissa().abs());
+                assert!(s.scale() <= d.scale());
+                assert!(s != d || s.scale() != d.scale());
+            }
+            assert_eq!(d.shrink().next().map(|s| s.is_zero()),
+                       if d.is_zero() { None } else { Some(true) });
+        }
+        let d = Decimal::new(-125, 2);
+        let shrunk: Vec<Decimal> = d.shrink().collect();
+        assert_eq!(shrunk[..2], [Decimal::new(0, 2), Decimal::new(125, 2)]);
+        assert!(shrunk.contains(&Decimal::new(-125, 0)));
+        assert!(shrunk.contains(&Decimal::new(-124, 2)));
+        assert_eq!(Decimal::new(0, 5).shrink().count(), 0);
+    }
+
     #[cfg(feature = "collect_impls")]
     #[test]
     fn triemaps() {
@@ -654,6 +4129,57 @@ mod test {
         }
     }
 
+    #[test]
+    fn unique_map_entries() {
+        let mut g = StdGen::new(rand::thread_rng(), 50);
+        for _ in 0..100 {
+            let mut seen = HashSet::new();
+            let entries: Vec<(u8, ())> =
+                unique_entries(&mut g, |k: &u8| seen.insert(*k));
+            let map: HashMap<u8, ()> = entries.clone().into_iter().collect();
+            assert_eq!(map.len(), entries.len());
+
+            let mut seen = BTreeSet::new();
+            let entries: Vec<(bool, u8)> =
+                unique_entries(&mut g, |k: &bool| seen.insert(*k));
+            let map: BTreeMap<bool, u8> =
+                entries.clone().into_iter().collect();
+            assert_eq!(map.len(), entries.len());
+        }
+    }
+
+    #[test]
+    fn btreemaps() {
+        fn map(entries: &[(usize, bool)]) -> BTreeMap<usize, bool> {
+            entries.iter().cloned().collect()
+        }
+        eq(map(&[]), vec![]);
+        eq(map(&[(1, true)]),
+           vec![map(&[]), map(&[(0, true)]), map(&[(1, false)])]);
+        // Shrinking a key can make it collide with another one.
+        eq(map(&[(0, true), (1, false)]),
+           vec![map(&[]), map(&[(0, true)]), map(&[(1, false)]),
+                map(&[(0, false), (1, false)]), map(&[(0, false)])]);
+    }
+
+    #[test]
+    fn btreesets() {
+        fn set(xs: &[isize]) -> BTreeSet<isize> {
+            xs.iter().cloned().collect()
+        }
+        eq(set(&[]), vec![]);
+        eq(set(&[1]), vec![set(&[]), set(&[0])]);
+        eq(set(&[0, 1]), vec![set(&[]), set(&[0]), set(&[1])]);
+    }
+
+    #[test]
+    fn hashsets() {
+        ordered_eq({let it: HashSet<isize> = HashSet::new(); it}, vec![]);
+        ordered_eq({let mut s = HashSet::new(); s.insert(1isize); s},
+                   vec![HashSet::new(),
+                        {let mut s = HashSet::new(); s.insert(0); s}]);
+    }
+
     #[test]
     fn hashmaps() {
         ordered_eq({let it: HashMap<usize, isize> = HashMap::new(); it}, vec![]);
@@ -672,19 +4198,121 @@ mod test {
         }
     }
 
+    /// The FNV-1a hash, to test maps and sets with a custom hasher.
+    struct Fnv(u64);
+
+    impl Default for Fnv {
+        fn default() -> Fnv { Fnv(0xcbf29ce484222325) }
+    }
+
+    impl Hasher for Fnv {
+        fn finish(&self) -> u64 { self.0 }
+        fn write(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 = (self.0 ^ b as u64).wrapping_mul(0x100000001b3);
+            }
+        }
+    }
+
+    #[test]
+    fn custom_hashers() {
+        type FnvMap = HashMap<u32, i8, BuildHasherDefault<Fnv>>;
+        type FnvSet = HashSet<u32, BuildHasherDefault<Fnv>>;
+        let mut g = StdGen::new(rand::thread_rng(), 100);
+        let maps: Vec<FnvMap> =
+            (0..10).map(|_| Arbitrary::arbitrary(&mut g)).collect();
+        assert!(maps.iter().any(|m| !m.is_empty()));
+        let sets: Vec<FnvSet> =
+            (0..10).map(|_| Arbitrary::arbitrary(&mut g)).collect();
+        assert!(sets.iter().any(|s| !s.is_empty()));
+
+        let mut m = FnvMap::default();
+        m.insert(1, 0);
+        let shrunk: Vec<FnvMap> = m.shrink().collect();
+        assert_eq!(shrunk.len(), 2);
+        assert!(shrunk[0].is_empty() && shrunk[1].get(&0) == Some(&0));
+    }
+
     #[test]
     fn chars() {
         eq('a', vec![]);
+        eq('c', vec!['a', 'b']);
+        eq('Z', vec!['a', 'b', 'c', 'z', 'A', 'B', 'C']);
+        eq('5', vec!['a', 'b', 'c', 'A', 'B', 'C', '1', '2', '3']);
+        eq(' ', vec!['a', 'b', 'c', 'A', 'B', 'C', '1', '2', '3']);
+        assert_eq!('\u{e9}'.shrink().take(11).collect::<String>(),
+                   "abcABC123 \0");
+
+        // Repeatedly taking the last shrunk value must still terminate.
+        let mut c = '\u{e9}';
+        while let Some(x) = c.shrink().last() {
+            c = x;
+        }
+        assert_eq!(c, 'a');
     }
 
     #[test]
     fn strs() {
         eq("".to_string(), vec![]);
-        eq("A".to_string(), vec!["".to_string()]);
-        eq("ABC".to_string(), vec!["".to_string(),
-                                   "AB".to_string(),
-                                   "BC".to_string(),
-                                   "AC".to_string()]);
+        eq("A".to_string(), vec!["".to_string(), "a".to_string(),
+                                 "b".to_string(), "c".to_string()]);
+        let strs = |ss: &[&str]| -> Vec<String> {
+            ss.iter().map(|s| s.to_string()).collect()
+        };
+        eq("ABC".to_string(), strs(&["", "A", "AB", "BC", "AC",
+                                     "aBC", "bBC", "cBC",
+                                     "AaC", "AbC", "AcC", "AAC",
+                                     "ABa", "ABb", "ABc", "ABA", "ABB"]));
+    }
+
+    #[test]
+    fn asciis() {
+        let mut g = gen();
+        for _ in 0..1000 {
+            let Ascii(c): Ascii = Arbitrary::arbitrary(&mut g);
+            assert!((c as u32) < 128);
+            assert!(Ascii(c).shrink().all(|Ascii(c)| (c as u32) < 128));
+            let AsciiString(s): AsciiString = Arbitrary::arbitrary(&mut g);
+            assert!(s.chars().all(|c| (c as u32) < 128));
+            assert!(AsciiString(s).shrink().all(|AsciiString(s)| {
+                s.chars().all(|c| (c as u32) < 128)
+            }));
+        }
+        eq(Ascii('a'), vec![]);
+        eq(Ascii('\x7f'), vec![
+            Ascii('a'), Ascii('b'), Ascii('c'), Ascii('A'), Ascii('B'),
+            Ascii('C'), Ascii('1'), Ascii('2'), Ascii('3'), Ascii(' '),
+        ]);
+        eq(AsciiString("b".to_string()), vec![
+            AsciiString("".to_string()), AsciiString("a".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn boxed_strs() {
+        let boxed = |s: &str| s.to_string().into_boxed_str();
+        eq(boxed(""), vec![]);
+        eq(boxed("A"), vec![boxed(""), boxed("a"), boxed("b"), boxed("c")]);
+        eq(boxed("ab"), vec![boxed(""), boxed("b"), boxed("a"),
+                             boxed("aa")]);
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn semvers() {
+        let zero = Version::parse("0.0.0").unwrap();
+        eq(zero.clone(), vec![]);
+
+        // Repeatedly taking the first shrunk value must end at `0.0.0`
+        // without any pre-release or build metadata.
+        let mut v = Version::parse("3.1.4-alpha.7+build.5").unwrap();
+        loop {
+            match v.shrink().next() {
+                None => break,
+                Some(sv) => v = sv,
+            }
+        }
+        assert_eq!(v.to_string(), "0.0.0");
     }
 
     // All this jazz is for testing set equality on the results of a shrinker.
@@ -0,0 +1,2317 @@
+use std::char;
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+use std::num::Wrapping;
+
+use super::{Arbitrary, Gen, Shrink};
+use arbitrary::{gen_len, vec_removals};
+
+use self::ArithOp::{Add, Sub, Mul, Div, Rem};
+use self::ArithOutcome::{InRange, Overflow, DivByZero};
+
+/// An arithmetic operation performed by an `ArithCase`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArithOp { Add, Sub, Mul, Div, Rem }
+
+/// What happens when an `ArithCase` is evaluated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArithOutcome {
+    /// The result is representable in the operand type.
+    InRange,
+    /// The result overflows the operand type.
+    Overflow,
+    /// The operation is a division or remainder by zero.
+    DivByZero,
+}
+
+/// An arithmetic operation on two operands of an integer type `T`, labeled
+/// with the outcome of evaluating it.
+///
+/// This is useful for testing code that relies on `checked_*` or
+/// `wrapping_*` arithmetic. Divisions oversample zero divisors and
+/// multiplications oversample operands close to `T::MAX`, so that both
+/// overflow and division by zero are common.
+///
+/// Shrinking only yields cases with the same `outcome`.
+#[derive(Clone, Debug)]
+pub struct ArithCase<T> {
+    pub a: T,
+    pub b: T,
+    pub op: ArithOp,
+    pub outcome: ArithOutcome,
+}
+
+macro_rules! arith_case {
+    ($($ty:ident),*) => {
+        $(
+            impl ArithCase<$ty> {
+                /// Creates a new case, computing its outcome.
+                pub fn new(a: $ty, b: $ty, op: ArithOp) -> ArithCase<$ty> {
+                    let mut case = ArithCase {
+                        a: a, b: b, op: op, outcome: InRange,
+                    };
+                    case.outcome = match op {
+                        Div|Rem if b == 0 => DivByZero,
+                        _ if case.checked().is_none() => Overflow,
+                        _ => InRange,
+                    };
+                    case
+                }
+
+                /// Evaluates this case with checked arithmetic.
+                pub fn checked(&self) -> Option<$ty> {
+                    match self.op {
+                        Add => self.a.checked_add(self.b),
+                        Sub => self.a.checked_sub(self.b),
+                        Mul => self.a.checked_mul(self.b),
+                        Div => self.a.checked_div(self.b),
+                        Rem => self.a.checked_rem(self.b),
+                    }
+                }
+            }
+
+            impl Arbitrary for ArithCase<$ty> {
+                fn arbitrary<G: Gen>(g: &mut G) -> ArithCase<$ty> {
+                    use std::$ty::{MIN, MAX};
+
+                    let op = *g.choose(&[Add, Sub, Mul, Div, Rem]).unwrap();
+                    let (a, b) = match op {
+                        Add|Sub if g.gen() => (g.gen(), g.gen()),
+                        Mul if g.gen() => {
+                            let a = MAX - g.gen_range(0, 8) as $ty;
+                            (a, g.gen_range(0, 8) as $ty)
+                        }
+                        Div|Rem if g.gen() => (Arbitrary::arbitrary(g), 0),
+                        // For signed types, this is the only division that
+                        // overflows.
+                        Div|Rem if g.gen_weighted_bool(4) => {
+                            (MIN, (0 as $ty).wrapping_sub(1))
+                        }
+                        _ => (Arbitrary::arbitrary(g),
+                              Arbitrary::arbitrary(g)),
+                    };
+                    ArithCase::<$ty>::new(a, b, op)
+                }
+            }
+
+            impl Shrink for ArithCase<$ty> {
+                fn shrink(&self)
+                         -> Box<Iterator<Item=ArithCase<$ty>>+'static> {
+                    let (op, outcome) = (self.op, self.outcome);
+                    let cases = (self.a, self.b).shrink()
+                        .map(move |(a, b)| ArithCase::<$ty>::new(a, b, op));
+                    Box::new(cases.filter(move |c| c.outcome == outcome))
+                }
+            }
+        )*
+    }
+}
+
+arith_case! {
+    usize, u8, u16, u32, u64, isize, i8, i16, i32, i64
+}
+
+/// A sequence of wrapping additions, subtractions and multiplications to
+/// apply to an accumulator of type `Wrapping<T>`.
+///
+/// This is useful for testing code that reduces a log of operations, e.g.,
+/// to check that additions can be applied in any order or that adding zero
+/// and multiplying by one do nothing. Only `Add`, `Sub` and `Mul` are
+/// generated.
+///
+/// Shrinking removes steps, then simplifies operands of additions and
+/// subtractions toward zero and operands of multiplications toward one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WrappingOps<T>(pub Vec<(ArithOp, T)>);
+
+macro_rules! wrapping_ops {
+    ($($ty:ident),*) => {
+        $(
+            impl WrappingOps<$ty> {
+                /// Applies every step, in order, to `acc`.
+                ///
+                /// # Panics
+                ///
+                /// Panics if a step is a division or a remainder.
+                pub fn apply(&self, acc: Wrapping<$ty>) -> Wrapping<$ty> {
+                    self.0.iter().fold(acc, |acc, &(op, x)| {
+                        match op {
+                            Add => acc + Wrapping(x),
+                            Sub => acc - Wrapping(x),
+                            Mul => acc * Wrapping(x),
+                            Div|Rem => panic!("not a wrapping op: {:?}", op),
+                        }
+                    })
+                }
+            }
+
+            impl Arbitrary for WrappingOps<$ty> {
+                fn arbitrary<G: Gen>(g: &mut G) -> WrappingOps<$ty> {
+                    let len = gen_len(g);
+                    WrappingOps((0..len).map(|_| {
+                        let op = *g.choose(&[Add, Sub, Mul]).unwrap();
+                        (op, Arbitrary::arbitrary(g))
+                    }).collect())
+                }
+            }
+
+            impl Shrink for WrappingOps<$ty> {
+                fn shrink(&self)
+                         -> Box<Iterator<Item=WrappingOps<$ty>>+'static> {
+                    let mut xss = vec_removals(&self.0);
+                    for (i, &(op, x)) in self.0.iter().enumerate() {
+                        let xs: Vec<$ty> = match op {
+                            Mul if x == 0 || x == 1 => vec![],
+                            Mul => Some(1).into_iter()
+                                          .chain(x.shrink()
+                                                  .filter(|&y| y > 1))
+                                          .collect(),
+                            _ => x.shrink().collect(),
+                        };
+                        for y in xs {
+                            let mut steps = self.0.clone();
+                            steps[i] = (op, y);
+                            xss.push(steps);
+                        }
+                    }
+                    Box::new(xss.into_iter().map(WrappingOps))
+                }
+            }
+        )*
+    }
+}
+
+wrapping_ops! {
+    usize, u8, u16, u32, u64, isize, i8, i16, i32, i64
+}
+
+/// A `VecDeque` whose shrinker also tries rotating it.
+///
+/// This is useful for testing code whose behavior should not depend on where
+/// a ring buffer starts. Before shrinking the elements, the deque is rotated
+/// to its lexicographically smallest rotation, so that minimal
+/// counterexamples are reported in a canonical form.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RotatableDeque<T>(pub VecDeque<T>);
+
+impl<T: Arbitrary + Ord> Arbitrary for RotatableDeque<T> {
+    fn arbitrary<G: Gen>(g: &mut G) -> RotatableDeque<T> {
+        let xs: Vec<T> = Arbitrary::arbitrary(g);
+        RotatableDeque(xs.into_iter().collect())
+    }
+}
+
+impl<T: Arbitrary + Ord> Shrink for RotatableDeque<T> {
+    fn shrink(&self) -> Box<Iterator<Item=RotatableDeque<T>>+'static> {
+        let xs: Vec<T> = self.0.iter().cloned().collect();
+        let canonical = min_rotation(&xs);
+        let rotated = if canonical != xs {
+            Some(RotatableDeque(canonical.into_iter().collect()))
+        } else {
+            None
+        };
+        let shrunk = xs.shrink()
+            .map(|xs| RotatableDeque(xs.into_iter().collect()));
+        Box::new(rotated.into_iter().chain(shrunk))
+    }
+}
+
+/// Returns the lexicographically smallest rotation of `xs`.
+fn min_rotation<T: Clone + Ord>(xs: &[T]) -> Vec<T> {
+    (0..xs.len())
+        .map(|i| xs[i..].iter().chain(xs[..i].iter()).cloned().collect())
+        .min()
+        .unwrap_or(vec![])
+}
+
+/// An RFC 6901 JSON pointer such as `/foo/0/a~1b`, labeled with whether it
+/// is well-formed.
+///
+/// Most generated pointers are well-formed, with `~` and `/` inside
+/// reference tokens escaped as `~0` and `~1`. About one in five contains a
+/// bad escape sequence (like `~2` or a trailing `~`) instead.
+///
+/// Shrinking reduces the number of reference tokens and only yields pointers
+/// with the same label.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct JsonPointer {
+    pub pointer: String,
+    pub valid: bool,
+}
+
+impl JsonPointer {
+    fn new(pointer: String) -> JsonPointer {
+        let valid = is_valid_json_pointer(&pointer);
+        JsonPointer { pointer: pointer, valid: valid }
+    }
+}
+
+impl Arbitrary for JsonPointer {
+    fn arbitrary<G: Gen>(g: &mut G) -> JsonPointer {
+        const CHARS: &'static [char] =
+            &['a', 'b', 'z', '0', '1', ' ', '~', '/'];
+        let ntokens = { let s = g.size(); g.gen_range(0, cmp::min(s, 8) + 1) };
+        let mut pointer = String::new();
+        for _ in 0..ntokens {
+            pointer.push('/');
+            for _ in 0..g.gen_range(0, 6) {
+                match *g.choose(CHARS).unwrap() {
+                    '~' => pointer.push_str("~0"),
+                    '/' => pointer.push_str("~1"),
+                    c => pointer.push(c),
+                }
+            }
+        }
+        if g.gen_weighted_bool(5) {
+            // An escape at the start of a token can't be completed by
+            // whatever follows it, but a lone `~` can only go at the end.
+            let starts: Vec<usize> = pointer.match_indices('/')
+                                            .map(|(i, _)| i + 1)
+                                            .collect();
+            match g.choose(&starts) {
+                Some(&i) if g.gen() => {
+                    let bad = *g.choose(&["~2", "~a", "~/"]).unwrap();
+                    pointer.insert_str(i, bad);
+                }
+                _ => pointer.push_str("/~"),
+            }
+        }
+        JsonPointer::new(pointer)
+    }
+}
+
+impl Shrink for JsonPointer {
+    fn shrink(&self) -> Box<Iterator<Item=JsonPointer>+'static> {
+        let valid = self.valid;
+        let tokens: Vec<String> = self.pointer.split('/')
+                                              .skip(1)
+                                              .map(|t| t.to_string())
+                                              .collect();
+        let pointers = tokens.shrink().map(|ts| {
+            JsonPointer::new(ts.iter().map(|t| format!("/{}", t)).collect())
+        });
+        Box::new(pointers.filter(move |p| p.valid == valid))
+    }
+}
+
+fn is_valid_json_pointer(s: &str) -> bool {
+    if !s.is_empty() && !s.starts_with('/') {
+        return false;
+    }
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '~' {
+            match chars.next() {
+                Some('0') | Some('1') => {}
+                _ => return false,
+            }
+        }
+    }
+    true
+}
+
+/// A string that is either valid standard base64 or deliberately invalid,
+/// labeled with which one it is.
+///
+/// Valid strings are the padded encoding of random bytes, so any unused
+/// bits in the last character are zero. About half of the strings are
+/// invalid instead: a character is dropped (so the length is not a multiple
+/// of four), a character is replaced with one outside the alphabet, or the
+/// last group of four characters has three `=`.
+///
+/// Shrinking removes groups of four characters from valid strings and
+/// single characters from invalid ones, and only yields strings with the
+/// same label.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Base64String {
+    pub encoded: String,
+    pub valid: bool,
+}
+
+const BASE64_CHARS: &'static [u8] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl Base64String {
+    fn new(encoded: String) -> Base64String {
+        let valid = is_valid_base64(&encoded);
+        Base64String { encoded: encoded, valid: valid }
+    }
+}
+
+impl Arbitrary for Base64String {
+    fn arbitrary<G: Gen>(g: &mut G) -> Base64String {
+        let mut encoded = encode_base64(&arbitrary_bytes(g));
+        if g.gen() {
+            return Base64String::new(encoded);
+        }
+        match g.gen_range(0, 3) {
+            0 => {
+                if encoded.pop().is_none() {
+                    encoded.push('A');
+                }
+            }
+            1 => {
+                let bad = *g.choose(&['!', '-', '_', '.', ' ']).unwrap();
+                encoded = replace_random_char(g, &encoded, bad);
+            }
+            _ => {
+                let len = encoded.len();
+                encoded.truncate(len.saturating_sub(3));
+                if encoded.is_empty() {
+                    encoded.push('A');
+                }
+                encoded.push_str("===");
+            }
+        }
+        Base64String::new(encoded)
+    }
+}
+
+impl Shrink for Base64String {
+    fn shrink(&self) -> Box<Iterator<Item=Base64String>+'static> {
+        let valid = self.valid;
+        let unit = if valid { 4 } else { 1 };
+        Box::new(shrink_units(&self.encoded, unit)
+                     .into_iter()
+                     .map(Base64String::new)
+                     .filter(move |b| b.valid == valid))
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut s = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| {
+            n | (b as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let c = BASE64_CHARS[(n >> (18 - 6 * i)) as usize & 63];
+                s.push(c as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+fn is_valid_base64(s: &str) -> bool {
+    let s = s.as_bytes();
+    if s.len() % 4 != 0 {
+        return false;
+    }
+    let pad = s.iter().rev().take_while(|&&c| c == b'=').count();
+    if pad > 2 {
+        return false;
+    }
+    let mut values = Vec::with_capacity(s.len());
+    for c in &s[..s.len() - pad] {
+        match BASE64_CHARS.iter().position(|b| b == c) {
+            Some(v) => values.push(v),
+            None => return false,
+        }
+    }
+    // The bits of the last character that don't make up a whole byte must
+    // be zero, or else two strings would decode to the same bytes.
+    let unused = if pad == 0 { 0 } else { (1 << (2 * pad)) - 1 };
+    values.last().map_or(true, |&v| v & unused == 0)
+}
+
+/// A string that is either valid hexadecimal or deliberately invalid,
+/// labeled with which one it is.
+///
+/// Valid strings encode random bytes with two digits each, either all in
+/// lowercase or all in uppercase. About half of the strings are invalid
+/// instead: they either have an odd length or contain a character that
+/// isn't a hexadecimal digit.
+///
+/// Shrinking removes pairs of digits from valid strings and single
+/// characters from invalid ones, and only yields strings with the same
+/// label.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HexString {
+    pub encoded: String,
+    pub valid: bool,
+}
+
+impl HexString {
+    fn new(encoded: String) -> HexString {
+        let valid = encoded.len() % 2 == 0
+                    && encoded.chars().all(|c| c.is_digit(16));
+        HexString { encoded: encoded, valid: valid }
+    }
+}
+
+impl Arbitrary for HexString {
+    fn arbitrary<G: Gen>(g: &mut G) -> HexString {
+        let mut encoded: String =
+            arbitrary_bytes(g).iter().map(|b| format!("{:02x}", b)).collect();
+        if g.gen() {
+            encoded = encoded.to_uppercase();
+        }
+        if g.gen() {
+            return HexString::new(encoded);
+        }
+        if g.gen() {
+            if encoded.pop().is_none() {
+                encoded.push('a');
+            }
+        } else {
+            let bad = *g.choose(&['g', 'x', 'G', '-', ' ']).unwrap();
+            encoded = replace_random_char(g, &encoded, bad);
+        }
+        HexString::new(encoded)
+    }
+}
+
+impl Shrink for HexString {
+    fn shrink(&self) -> Box<Iterator<Item=HexString>+'static> {
+        let valid = self.valid;
+        let unit = if valid { 2 } else { 1 };
+        Box::new(shrink_units(&self.encoded, unit)
+                     .into_iter()
+                     .map(HexString::new)
+                     .filter(move |h| h.valid == valid))
+    }
+}
+
+/// Returns up to `size` random bytes, which aren't limited by `size`.
+fn arbitrary_bytes<G: Gen>(g: &mut G) -> Vec<u8> {
+    let len = { let s = g.size(); g.gen_range(0, s + 1) };
+    (0..len).map(|_| g.gen()).collect()
+}
+
+/// Replaces a random character of the ASCII string `s` with `c`, or returns
+/// `c` if `s` is empty.
+fn replace_random_char<G: Gen>(g: &mut G, s: &str, c: char) -> String {
+    if s.is_empty() {
+        return c.to_string();
+    }
+    let i = g.gen_range(0, s.len());
+    format!("{}{}{}", &s[..i], c, &s[i + 1..])
+}
+
+/// Returns `s` with runs of the groups of `unit` characters that it is
+/// made of removed.
+fn shrink_units(s: &str, unit: usize) -> Vec<String> {
+    let chars: Vec<char> = s.chars().collect();
+    let units: Vec<Vec<char>> =
+        chars.chunks(unit).map(|c| c.to_vec()).collect();
+    vec_removals(&units).into_iter()
+                        .map(|us| us.into_iter().flat_map(|u| u).collect())
+                        .collect()
+}
+
+/// A `Vec` whose elements are drawn from a small pool of values.
+///
+/// Vectors of most element types almost never contain duplicates when
+/// generated with `Arbitrary`. Here, a pool of one to four random values is
+/// generated first and every element is picked from it, so duplicates are
+/// the norm. This is useful for testing deduplication, grouping and
+/// counting code.
+///
+/// Shrinking removes elements and shrinks values of the pool, replacing
+/// every occurrence of a value at once.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VecFromPool<A>(pub Vec<A>);
+
+impl<A: Arbitrary + PartialEq> Arbitrary for VecFromPool<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> VecFromPool<A> {
+        let pool: Vec<A> = (0..g.gen_range(1, 5))
+                               .map(|_| Arbitrary::arbitrary(g))
+                               .collect();
+        let len = gen_len(g);
+        VecFromPool((0..len).map(|_| g.choose(&pool).unwrap().clone())
+                            .collect())
+    }
+}
+
+impl<A: Arbitrary + PartialEq> Shrink for VecFromPool<A> {
+    fn shrink(&self) -> Box<Iterator<Item=VecFromPool<A>>+'static> {
+        let mut xss = vec_removals(&self.0);
+        let mut pool: Vec<&A> = vec![];
+        for x in self.0.iter() {
+            if pool.contains(&x) {
+                continue;
+            }
+            pool.push(x);
+            for sx in x.shrink() {
+                xss.push(self.0.iter().map(|y| {
+                    if y == x { sx.clone() } else { y.clone() }
+                }).collect());
+            }
+        }
+        Box::new(xss.into_iter().map(VecFromPool))
+    }
+}
+
+/// A `Vec` whose shrinker also tries collapsing runs of equal adjacent
+/// elements.
+///
+/// Before the usual `Vec` shrinks, every run of equal adjacent elements is
+/// replaced by a single element, e.g., `[1, 1, 1, 2, 2]` shrinks to
+/// `[1, 2]` first. This often gives a much more readable counterexample for
+/// bugs that depend on runs, without requiring `PartialEq` for every `Vec`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RunCollapsingVec<A>(pub Vec<A>);
+
+impl<A: Arbitrary + PartialEq> Arbitrary for RunCollapsingVec<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> RunCollapsingVec<A> {
+        RunCollapsingVec(Arbitrary::arbitrary(g))
+    }
+}
+
+impl<A: Arbitrary + PartialEq> Shrink for RunCollapsingVec<A> {
+    fn shrink(&self) -> Box<Iterator<Item=RunCollapsingVec<A>>+'static> {
+        let mut collapsed = self.0.clone();
+        collapsed.dedup();
+        let first = if collapsed.len() < self.0.len() {
+            Some(collapsed)
+        } else {
+            None
+        };
+        Box::new(first.into_iter()
+                      .chain(self.0.shrink())
+                      .map(RunCollapsingVec))
+    }
+}
+
+/// A length-prefixed protocol message, labeled with whether its length
+/// field matches the length of its payload.
+///
+/// Most generated messages are well-framed. About one in five has a length
+/// field that lies about the payload length, which is useful for testing
+/// the error paths of framing parsers.
+///
+/// Shrinking shrinks the tag and the payload. The length field is adjusted
+/// so that every shrunk message keeps the same label.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FramedMessage {
+    pub tag: u8,
+    pub length: u32,
+    pub payload: Vec<u8>,
+    pub valid: bool,
+}
+
+impl FramedMessage {
+    /// Encodes this message as its tag, followed by its length field as a
+    /// big-endian `u32`, followed by its payload.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.tag];
+        for i in (0..4).rev() {
+            bytes.push((self.length >> (8 * i)) as u8);
+        }
+        bytes.extend(self.payload.iter().cloned());
+        bytes
+    }
+
+    /// Creates a message whose length field is off from the real length
+    /// of `payload` by `delta`, or `None` if such a length doesn't fit in
+    /// the length field.
+    fn with_delta(tag: u8, payload: Vec<u8>, delta: i64)
+                 -> Option<FramedMessage> {
+        let length = payload.len() as i64 + delta;
+        if length < 0 || length > ::std::u32::MAX as i64 {
+            return None;
+        }
+        Some(FramedMessage {
+            tag: tag,
+            length: length as u32,
+            payload: payload,
+            valid: delta == 0,
+        })
+    }
+}
+
+impl Arbitrary for FramedMessage {
+    fn arbitrary<G: Gen>(g: &mut G) -> FramedMessage {
+        let tag = g.gen();
+        let payload: Vec<u8> = Arbitrary::arbitrary(g);
+        let delta = if g.gen_weighted_bool(5) {
+            let d = g.gen_range(1, 16);
+            if g.gen() || (payload.len() as i64) < d { d } else { -d }
+        } else {
+            0
+        };
+        FramedMessage::with_delta(tag, payload, delta).unwrap()
+    }
+}
+
+impl Shrink for FramedMessage {
+    fn shrink(&self) -> Box<Iterator<Item=FramedMessage>+'static> {
+        let delta = self.length as i64 - self.payload.len() as i64;
+        let (tag, payload) = (self.tag, self.payload.clone());
+        let tags = self.tag.shrink().filter_map(move |t| {
+            FramedMessage::with_delta(t, payload.clone(), delta)
+        });
+        let payloads = self.payload.shrink().filter_map(move |p| {
+            FramedMessage::with_delta(tag, p, delta)
+        });
+        Box::new(tags.chain(payloads))
+    }
+}
+
+/// A list of half-open intervals `[start, end)` with `start <= end`,
+/// labeled with the number of pairs of intervals that overlap.
+///
+/// The `Arbitrary` impl picks a random density. Use `with_density` to pick
+/// one explicitly.
+///
+/// Shrinking removes intervals, narrows them and moves them toward zero.
+/// Every shrunk interval still satisfies `start <= end`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Intervals<T> {
+    pub intervals: Vec<(T, T)>,
+    pub overlaps: usize,
+}
+
+macro_rules! intervals {
+    ($($ty:ident),*) => {
+        $(
+            impl Intervals<$ty> {
+                /// Creates a new list of intervals, counting how many of
+                /// them overlap.
+                ///
+                /// Empty intervals never overlap anything.
+                pub fn new(intervals: Vec<($ty, $ty)>) -> Intervals<$ty> {
+                    let mut overlaps = 0;
+                    for (i, &(s1, e1)) in intervals.iter().enumerate() {
+                        for &(s2, e2) in intervals[..i].iter() {
+                            let nonempty = s1 < e1 && s2 < e2;
+                            if nonempty && s1 < e2 && s2 < e1 {
+                                overlaps += 1;
+                            }
+                        }
+                    }
+                    Intervals { intervals: intervals, overlaps: overlaps }
+                }
+
+                /// Generates intervals whose width is at most `density`
+                /// times the width of the range they are drawn from, so
+                /// that a `density` close to `0.0` rarely produces
+                /// overlapping intervals and a `density` of `1.0` produces
+                /// many.
+                pub fn with_density<G: Gen>(g: &mut G, density: f64)
+                                           -> Intervals<$ty> {
+                    let density = density.max(0.0).min(1.0);
+                    let span = cmp::max(1, cmp::min(g.size(), 1 << 15));
+                    let max_width =
+                        cmp::max(1, (span as f64 * density) as usize);
+                    let n = g.gen_range(0, cmp::min(span, 20) + 1);
+                    let intervals = (0..n).map(|_| {
+                        let start = g.gen_range(0, span);
+                        let width = g.gen_range(0, max_width + 1);
+                        (start as $ty, (start + width) as $ty)
+                    }).collect();
+                    Intervals::<$ty>::new(intervals)
+                }
+            }
+
+            impl Arbitrary for Intervals<$ty> {
+                fn arbitrary<G: Gen>(g: &mut G) -> Intervals<$ty> {
+                    let density = g.gen();
+                    Intervals::<$ty>::with_density(g, density)
+                }
+            }
+
+            impl Shrink for Intervals<$ty> {
+                fn shrink(&self)
+                         -> Box<Iterator<Item=Intervals<$ty>>+'static> {
+                    let ivs = &self.intervals;
+                    let mut xss = vec_removals(ivs);
+                    for (i, &(start, end)) in ivs.iter().enumerate() {
+                        // The width of a signed interval such as
+                        // `(i64::MIN, i64::MAX)` doesn't fit in its type.
+                        // Such an interval is only shrunk by removing it.
+                        let width = match end.checked_sub(start) {
+                            Some(width) => width,
+                            None => continue,
+                        };
+                        for w in width.shrink() {
+                            let mut narrowed = ivs.clone();
+                            narrowed[i] = (start, start + w);
+                            xss.push(narrowed);
+                        }
+                        for s in start.shrink() {
+                            if let Some(e) = s.checked_add(width) {
+                                let mut moved = ivs.clone();
+                                moved[i] = (s, e);
+                                xss.push(moved);
+                            }
+                        }
+                    }
+                    Box::new(xss.into_iter().map(Intervals::<$ty>::new))
+                }
+            }
+        )*
+    }
+}
+
+intervals! {
+    usize, u32, u64, i32, i64
+}
+
+/// A rectangular `rows × cols` matrix stored as a vector of rows.
+///
+/// Every row in `data` has exactly `cols` elements, both when generated
+/// and on every shrink. Shrinking removes whole rows, then whole columns,
+/// then shrinks individual elements.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Matrix<T> {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<Vec<T>>,
+}
+
+impl<T: Arbitrary> Arbitrary for Matrix<T> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Matrix<T> {
+        let dim = (g.size() as f64).sqrt() as usize + 1;
+        let rows = g.gen_range(0, dim + 1);
+        let cols = g.gen_range(0, dim + 1);
+        let data = (0..rows).map(|_| {
+            (0..cols).map(|_| Arbitrary::arbitrary(g)).collect()
+        }).collect();
+        Matrix { rows: rows, cols: cols, data: data }
+    }
+}
+
+impl<T: Arbitrary> Shrink for Matrix<T> {
+    fn shrink(&self) -> Box<Iterator<Item=Matrix<T>>+'static> {
+        let mut ms = vec![];
+        let rows: Vec<usize> = (0..self.rows).collect();
+        for keep in vec_removals(&rows) {
+            ms.push(Matrix {
+                rows: keep.len(),
+                cols: self.cols,
+                data: keep.iter().map(|&i| self.data[i].clone()).collect(),
+            });
+        }
+        let cols: Vec<usize> = (0..self.cols).collect();
+        for keep in vec_removals(&cols) {
+            ms.push(Matrix {
+                rows: self.rows,
+                cols: keep.len(),
+                data: self.data.iter().map(|row| {
+                    keep.iter().map(|&j| row[j].clone()).collect()
+                }).collect(),
+            });
+        }
+        for (i, row) in self.data.iter().enumerate() {
+            for (j, x) in row.iter().enumerate() {
+                for sx in x.shrink() {
+                    let mut m = self.clone();
+                    m.data[i][j] = sx;
+                    ms.push(m);
+                }
+            }
+        }
+        Box::new(ms.into_iter())
+    }
+}
+
+/// The shapes of configuration keys generated for a `ConfigMap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyShape {
+    /// Like environment variables, e.g., `DATABASE_URL`.
+    UpperSnake,
+    /// Like dotted config paths, e.g., `server.http2.port`.
+    Dotted,
+    /// Like command line flags, e.g., `max-connections`.
+    Kebab,
+    /// A single lowercase word, e.g., `port`, which could be either a
+    /// `Dotted` or a `Kebab` key.
+    Word,
+}
+
+impl KeyShape {
+    /// Returns the shape of `key`, or `None` if it doesn't have one.
+    ///
+    /// Every word in a key starts with a letter and is followed by letters
+    /// and digits. A key made of a single lowercase word is a `Word`.
+    pub fn of(key: &str) -> Option<KeyShape> {
+        let shape =
+            if key.chars().any(|c| c.is_uppercase()) {
+                KeyShape::UpperSnake
+            } else if key.contains('-') {
+                KeyShape::Kebab
+            } else if key.contains('.') {
+                KeyShape::Dotted
+            } else {
+                KeyShape::Word
+            };
+        let upper = shape == KeyShape::UpperSnake;
+        let letter = |c: char| {
+            if upper { c >= 'A' && c <= 'Z' } else { c >= 'a' && c <= 'z' }
+        };
+        let valid = key.split(shape.separator()).all(|word| {
+            match word.chars().next() {
+                Some(c) if letter(c) => {}
+                _ => return false,
+            }
+            word.chars().all(|c| letter(c) || c.is_digit(10))
+        });
+        if valid { Some(shape) } else { None }
+    }
+
+    fn separator(self) -> char {
+        match self {
+            KeyShape::UpperSnake => '_',
+            KeyShape::Dotted => '.',
+            KeyShape::Kebab => '-',
+            // A `Word` is never split or joined, so any separator that
+            // can't appear in it will do.
+            KeyShape::Word => '.',
+        }
+    }
+
+    fn join(self, words: &[String]) -> String {
+        let key = words.connect(&*self.separator().to_string());
+        if self == KeyShape::UpperSnake { key.to_uppercase() } else { key }
+    }
+}
+
+/// A map of configuration keys to arbitrary values.
+///
+/// Every key has one of the shapes in `KeyShape`, so that code normalizing
+/// or parsing keys is tested with realistic input instead of random
+/// Unicode.
+///
+/// Shrinking removes entries, shrinks values and shortens keys by dropping
+/// words or characters, without changing their shape. The one exception is
+/// a lowercase key shrunk to a single word, which is a `KeyShape::Word`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigMap(pub HashMap<String, String>);
+
+impl Arbitrary for ConfigMap {
+    fn arbitrary<G: Gen>(g: &mut G) -> ConfigMap {
+        const SHAPES: &'static [KeyShape] =
+            &[KeyShape::UpperSnake, KeyShape::Dotted, KeyShape::Kebab];
+        const FIRST: &'static [u8] = b"abcdefghijklmnopqrstuvwxyz";
+        const REST: &'static [u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+        let len = gen_len(g);
+        let mut map = HashMap::new();
+        for _ in 0..len {
+            let shape = *g.choose(SHAPES).unwrap();
+            let words: Vec<String> = (0..g.gen_range(1, 5)).map(|_| {
+                let mut word = vec![*g.choose(FIRST).unwrap()];
+                for _ in 0..g.gen_range(0, 8) {
+                    word.push(*g.choose(REST).unwrap());
+                }
+                String::from_utf8(word).unwrap()
+            }).collect();
+            map.insert(shape.join(&words), Arbitrary::arbitrary(g));
+        }
+        ConfigMap(map)
+    }
+}
+
+impl Shrink for ConfigMap {
+    fn shrink(&self) -> Box<Iterator<Item=ConfigMap>+'static> {
+        let mut entries: Vec<(String, String)> =
+            self.0.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        entries.sort();
+        let removals = vec_removals(&entries).into_iter().map(|es| {
+            ConfigMap(es.into_iter().collect())
+        });
+        let map = self.0.clone();
+        let keys = entries.clone().into_iter().flat_map(move |(k, v)| {
+            let map = map.clone();
+            let sks: Vec<String> = shrink_config_key(&k).into_iter()
+                .filter(|sk| !map.contains_key(sk))
+                .collect();
+            sks.into_iter().map(move |sk| {
+                let mut map = map.clone();
+                map.remove(&k);
+                map.insert(sk, v.clone());
+                ConfigMap(map)
+            })
+        });
+        let map = self.0.clone();
+        let values = entries.into_iter().flat_map(move |(k, v)| {
+            let map = map.clone();
+            v.shrink().map(move |sv| {
+                let mut map = map.clone();
+                map.insert(k.clone(), sv);
+                ConfigMap(map)
+            })
+        });
+        Box::new(removals.chain(keys).chain(values))
+    }
+}
+
+/// Returns shorter keys with the same shape as `key`, or a single `Word`.
+fn shrink_config_key(key: &str) -> Vec<String> {
+    let shape = match KeyShape::of(key) {
+        None => return vec![],
+        Some(shape) => shape,
+    };
+    let words: Vec<String> = key.split(shape.separator())
+                                .map(|w| w.to_string())
+                                .collect();
+    let mut keys = vec![];
+    if words.len() > 1 {
+        for i in 0..words.len() {
+            let mut ws = words.clone();
+            ws.remove(i);
+            keys.push(shape.join(&ws));
+        }
+    }
+    for (i, word) in words.iter().enumerate() {
+        // Words are ASCII, so any length is a character boundary.
+        let mut lens = vec![1, word.len() / 2, word.len() - 1];
+        lens.retain(|&n| n > 0 && n < word.len());
+        lens.dedup();
+        for n in lens {
+            let mut ws = words.clone();
+            ws[i].truncate(n);
+            keys.push(shape.join(&ws));
+        }
+    }
+    keys
+}
+
+/// A set of bits stored in 64 bit words, like the ones used by bitmaps.
+///
+/// Generating random words sets about half of the bits, so sparse and dense
+/// sets are never tested. The `Arbitrary` impl instead picks one of a few
+/// regimes: completely empty, sparse, half full, dense or completely full.
+/// Use `with_density` to pick a density explicitly.
+///
+/// Shrinking clears bits until the set is empty. The number of words never
+/// changes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitSet(pub Vec<u64>);
+
+impl BitSet {
+    /// Generates a bit set in which every bit is set with probability
+    /// `density`.
+    ///
+    /// A `density` of `0.0` always produces an empty set and a `density` of
+    /// `1.0` always produces a set with every bit set.
+    pub fn with_density<G: Gen>(g: &mut G, density: f64) -> BitSet {
+        let nwords = { let s = g.size(); g.gen_range(0, s / 8 + 2) };
+        BitSet((0..nwords).map(|_| {
+            let mut word = 0;
+            for i in 0..64 {
+                if g.gen::<f64>() < density {
+                    word |= 1 << i;
+                }
+            }
+            word
+        }).collect())
+    }
+
+    /// Returns the number of bits that are set.
+    pub fn count_ones(&self) -> usize {
+        self.0.iter().fold(0, |n, w| n + w.count_ones() as usize)
+    }
+
+    /// Returns the fraction of bits that are set, or `0.0` if there are no
+    /// words.
+    pub fn density(&self) -> f64 {
+        if self.0.is_empty() {
+            return 0.0;
+        }
+        self.count_ones() as f64 / (self.0.len() * 64) as f64
+    }
+}
+
+impl Arbitrary for BitSet {
+    fn arbitrary<G: Gen>(g: &mut G) -> BitSet {
+        let density = match g.gen_range(0, 5) {
+            0 => 0.0,
+            1 => g.gen_range(0.0, 0.1),
+            2 => 0.5,
+            3 => g.gen_range(0.9, 1.0),
+            _ => 1.0,
+        };
+        BitSet::with_density(g, density)
+    }
+}
+
+impl Shrink for BitSet {
+    fn shrink(&self) -> Box<Iterator<Item=BitSet>+'static> {
+        if self.count_ones() == 0 {
+            return Box::new(None.into_iter());
+        }
+        let words = self.0.clone();
+        let empty = BitSet(vec![0; words.len()]);
+        let mut cleared_words = vec![];
+        for (i, &w) in words.iter().enumerate() {
+            if w != 0 && self.count_ones() > w.count_ones() as usize {
+                let mut ws = words.clone();
+                ws[i] = 0;
+                cleared_words.push(BitSet(ws));
+            }
+        }
+        let cleared_bits = (0..words.len() * 64).filter_map(move |i| {
+            let (w, bit) = (i / 64, 1 << (i % 64));
+            if words[w] & bit == 0 {
+                return None;
+            }
+            let mut ws = words.clone();
+            ws[w] &= !bit;
+            Some(BitSet(ws))
+        });
+        Box::new(Some(empty).into_iter()
+                            .chain(cleared_words.into_iter())
+                            .chain(cleared_bits))
+    }
+}
+
+/// A point on a probability simplex: a non-empty vector of non-negative
+/// probabilities that sum to `1.0` (up to rounding).
+///
+/// Some of the probabilities are sometimes exactly zero.
+///
+/// Shrinking removes categories and tries the uniform distribution. Every
+/// candidate is normalized again, so it is also a probability simplex.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Simplex(pub Vec<f64>);
+
+impl Simplex {
+    /// Scales `weights` so they sum to `1.0`, or returns `None` if they
+    /// sum to zero.
+    fn normalize(weights: Vec<f64>) -> Option<Simplex> {
+        let sum = weights.iter().fold(0.0, |a, &b| a + b);
+        if sum <= 0.0 {
+            return None;
+        }
+        Some(Simplex(weights.into_iter().map(|w| w / sum).collect()))
+    }
+}
+
+impl Arbitrary for Simplex {
+    fn arbitrary<G: Gen>(g: &mut G) -> Simplex {
+        let n = { let s = g.size(); g.gen_range(1, cmp::max(s, 1) + 1) };
+        let mut weights: Vec<f64> = (0..n).map(|_| {
+            if g.gen_weighted_bool(10) {
+                0.0
+            } else {
+                // Exponentially distributed weights give points that are
+                // uniformly distributed on the simplex.
+                -(1.0 - g.gen::<f64>()).ln()
+            }
+        }).collect();
+        if weights.iter().all(|&w| w == 0.0) {
+            let i = g.gen_range(0, n);
+            weights[i] = 1.0;
+        }
+        Simplex::normalize(weights).unwrap()
+    }
+}
+
+impl Shrink for Simplex {
+    fn shrink(&self) -> Box<Iterator<Item=Simplex>+'static> {
+        let n = self.0.len();
+        let mut xs = vec![];
+        let uniform = 1.0 / n as f64;
+        if self.0.iter().any(|&p| p != uniform) {
+            xs.push(Simplex(vec![uniform; n]));
+        }
+        if n > 1 {
+            for i in 0..n {
+                let mut ps = self.0.clone();
+                ps.remove(i);
+                xs.extend(Simplex::normalize(ps));
+            }
+        }
+        Box::new(xs.into_iter())
+    }
+}
+
+/// A map whose values are vectors, with a bound on the total number of
+/// elements in all of the vectors.
+///
+/// Generating a `HashMap<K, Vec<V>>` with `Arbitrary` can produce up to
+/// `size * size` elements. Here, the total is at most the size of the
+/// generator. Use `with_budget` to pick a different bound. Note that only
+/// the elements of the vectors are counted, not anything nested inside
+/// them.
+///
+/// Shrinking never increases the total number of elements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoundedNested<K: Eq + Hash, V>(pub HashMap<K, Vec<V>>);
+
+impl<K: Arbitrary + Eq + Hash, V: Arbitrary> BoundedNested<K, V> {
+    /// Generates a map whose vectors contain at most `budget` elements in
+    /// total.
+    pub fn with_budget<G: Gen>(g: &mut G, budget: usize)
+                              -> BoundedNested<K, V> {
+        let nkeys = g.gen_range(0, budget + 1);
+        let mut remaining = budget;
+        let mut map = HashMap::new();
+        for i in 0..nkeys {
+            // Give every remaining key an equal share on average.
+            let share = remaining / (nkeys - i);
+            let len = cmp::min(remaining, g.gen_range(0, 2 * share + 1));
+            remaining -= len;
+            let vs = (0..len).map(|_| Arbitrary::arbitrary(g)).collect();
+            map.insert(Arbitrary::arbitrary(g), vs);
+        }
+        BoundedNested(map)
+    }
+
+    /// Returns the total number of elements in all of the vectors.
+    pub fn elements(&self) -> usize {
+        self.0.values().fold(0, |n, vs| n + vs.len())
+    }
+}
+
+impl<K: Arbitrary + Eq + Hash, V: Arbitrary> Arbitrary
+        for BoundedNested<K, V> {
+    fn arbitrary<G: Gen>(g: &mut G) -> BoundedNested<K, V> {
+        let size = g.size();
+        BoundedNested::with_budget(g, size)
+    }
+}
+
+impl<K: Arbitrary + Eq + Hash, V: Arbitrary> Shrink
+        for BoundedNested<K, V> {
+    fn shrink(&self) -> Box<Iterator<Item=BoundedNested<K, V>>+'static> {
+        Box::new(self.0.shrink().map(BoundedNested))
+    }
+}
+
+/// A string whose length in bytes, rather than in characters, is bounded.
+///
+/// Characters are picked so that one, two, three and four byte UTF-8
+/// encodings are all equally common. The `Arbitrary` impl bounds the length
+/// by the size of the generator. Use `with_budget` to pick a different
+/// bound.
+///
+/// Shrinking removes characters and never makes the string longer.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteBoundedString(pub String);
+
+impl ByteBoundedString {
+    /// Generates a string that is at most `budget` bytes long.
+    ///
+    /// Characters are added until the next one doesn't fit, so the string
+    /// may be shorter than `budget` even if a shorter character would
+    /// still have fit.
+    pub fn with_budget<G: Gen>(g: &mut G, budget: usize)
+                              -> ByteBoundedString {
+        let target = g.gen_range(0, budget + 1);
+        let mut s = String::new();
+        loop {
+            let c = arbitrary_utf8_char(g);
+            if s.len() + c.len_utf8() > target {
+                return ByteBoundedString(s);
+            }
+            s.push(c);
+        }
+    }
+}
+
+impl Arbitrary for ByteBoundedString {
+    fn arbitrary<G: Gen>(g: &mut G) -> ByteBoundedString {
+        let size = g.size();
+        ByteBoundedString::with_budget(g, size)
+    }
+}
+
+impl Shrink for ByteBoundedString {
+    fn shrink(&self) -> Box<Iterator<Item=ByteBoundedString>+'static> {
+        let len = self.0.len();
+        Box::new(self.0.shrink()
+                       .filter(move |s| s.len() <= len)
+                       .map(ByteBoundedString))
+    }
+}
+
+/// A string of characters from all of Unicode, not just ASCII.
+///
+/// Like for `ByteBoundedString`, one, two, three and four byte UTF-8
+/// encodings are all equally common, so characters outside the Basic
+/// Multilingual Plane come up often. The number of characters is less than
+/// the size of the generator.
+///
+/// Shrinking is the same as for `String`, which works on characters, so it
+/// never splits one.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnicodeString(pub String);
+
+impl Arbitrary for UnicodeString {
+    fn arbitrary<G: Gen>(g: &mut G) -> UnicodeString {
+        let len = gen_len(g);
+        UnicodeString((0..len).map(|_| arbitrary_utf8_char(g)).collect())
+    }
+}
+
+impl Shrink for UnicodeString {
+    fn shrink(&self) -> Box<Iterator<Item=UnicodeString>+'static> {
+        Box::new(self.0.shrink().map(UnicodeString))
+    }
+}
+
+/// Returns a random character whose UTF-8 encoding is 1, 2, 3 or 4 bytes
+/// long, each with equal probability.
+fn arbitrary_utf8_char<G: Gen>(g: &mut G) -> char {
+    let (lo, hi) = match g.gen_range(1, 5) {
+        1 => (0x20, 0x7F),
+        2 => (0x80, 0x800),
+        3 => (0x800, 0x10000),
+        _ => (0x10000, 0x110000),
+    };
+    loop {
+        // Surrogates aren't characters, so try again.
+        if let Some(c) = char::from_u32(g.gen_range(lo, hi)) {
+            return c;
+        }
+    }
+}
+
+/// A vector together with the lengths of the chunks it is split into.
+///
+/// The chunk lengths always add up to the length of the vector, and there
+/// is always at least one chunk. Chunks may be empty. This is useful for
+/// checking that processing every chunk separately and combining the
+/// results is the same as processing the whole vector.
+///
+/// Shrinking removes whole chunks, merges adjacent chunks, removes the last
+/// element of a chunk and finally shrinks the elements, all without
+/// breaking the invariant above.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Partitioned<T>(pub Vec<T>, pub Vec<usize>);
+
+impl<T: Arbitrary> Arbitrary for Partitioned<T> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Partitioned<T> {
+        let xs: Vec<T> = Arbitrary::arbitrary(g);
+        let len = xs.len();
+        let ncuts = g.gen_range(0, len + 1);
+        let mut ends: Vec<usize> =
+            (0..ncuts).map(|_| g.gen_range(0, len + 1)).collect();
+        ends.sort();
+        ends.push(len);
+        let mut start = 0;
+        let chunks = ends.into_iter().map(|end| {
+            let n = end - start;
+            start = end;
+            n
+        }).collect();
+        Partitioned(xs, chunks)
+    }
+}
+
+impl<T: Arbitrary> Shrink for Partitioned<T> {
+    fn shrink(&self) -> Box<Iterator<Item=Partitioned<T>>+'static> {
+        let Partitioned(ref xs, ref chunks) = *self;
+        let without = |start: usize, n: usize| -> Vec<T> {
+            let mut ys = xs[..start].to_vec();
+            ys.extend(xs[start + n..].iter().cloned());
+            ys
+        };
+        let mut parts = vec![];
+        if !xs.is_empty() {
+            parts.push(Partitioned(vec![], vec![0]));
+        }
+        if chunks.len() > 1 {
+            // Removing an empty chunk is the same as merging it, below.
+            let mut start = 0;
+            for (i, &n) in chunks.iter().enumerate() {
+                if n > 0 {
+                    let mut cs = chunks.clone();
+                    cs.remove(i);
+                    parts.push(Partitioned(without(start, n), cs));
+                }
+                start += n;
+            }
+            for i in 1..chunks.len() {
+                // Merging into an empty chunk is the same as merging the
+                // empty chunk into the one before it.
+                if i > 1 && chunks[i - 1] == 0 {
+                    continue;
+                }
+                let mut cs = chunks.clone();
+                let n = cs.remove(i);
+                cs[i - 1] += n;
+                parts.push(Partitioned(xs.clone(), cs));
+            }
+        }
+        let mut start = 0;
+        for (i, &n) in chunks.iter().enumerate() {
+            if n > 0 {
+                let mut cs = chunks.clone();
+                cs[i] -= 1;
+                parts.push(Partitioned(without(start + n - 1, 1), cs));
+            }
+            start += n;
+        }
+        let (len, chunks) = (xs.len(), chunks.clone());
+        let shrunk = xs.shrink()
+                       .filter(move |ys| ys.len() == len)
+                       .map(move |ys| Partitioned(ys, chunks.clone()));
+        Box::new(parts.into_iter().chain(shrunk))
+    }
+}
+
+/// A vector of distinct IDs.
+///
+/// IDs are drawn from `0..2 * size`, so that small ones are common, and
+/// every duplicate is replaced with a fresh ID.
+///
+/// Shrinking removes IDs and shrinks individual IDs to values that aren't
+/// in the vector yet, so every shrunk vector is still free of duplicates.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UniqueIds(pub Vec<u64>);
+
+impl Arbitrary for UniqueIds {
+    fn arbitrary<G: Gen>(g: &mut G) -> UniqueIds {
+        let size = g.size();
+        let len = g.gen_range(0, size + 1);
+        let mut seen = HashSet::new();
+        let mut ids = Vec::with_capacity(len);
+        while ids.len() < len {
+            let id = g.gen_range(0, 2 * size as u64);
+            if seen.insert(id) {
+                ids.push(id);
+            }
+        }
+        UniqueIds(ids)
+    }
+}
+
+impl Shrink for UniqueIds {
+    fn shrink(&self) -> Box<Iterator<Item=UniqueIds>+'static> {
+        let ids = self.0.clone();
+        let values = (0..ids.len()).flat_map(move |i| {
+            let (taken, ids) = (ids.clone(), ids.clone());
+            ids[i].shrink()
+                  .filter(move |id| !taken.contains(id))
+                  .map(move |id| {
+                      let mut ids = ids.clone();
+                      ids[i] = id;
+                      UniqueIds(ids)
+                  })
+        });
+        Box::new(vec_removals(&self.0).into_iter()
+                                      .map(UniqueIds)
+                                      .chain(values))
+    }
+}
+
+/// A vector of distinct IDs in ascending order, e.g., as handed out by an
+/// allocator that never reuses an ID.
+///
+/// This is a sorted `UniqueIds`, and it shrinks the same way, keeping only
+/// the shrunk vectors that are still sorted.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MonotonicIds(pub Vec<u64>);
+
+impl Arbitrary for MonotonicIds {
+    fn arbitrary<G: Gen>(g: &mut G) -> MonotonicIds {
+        let UniqueIds(mut ids) = Arbitrary::arbitrary(g);
+        ids.sort();
+        MonotonicIds(ids)
+    }
+}
+
+impl Shrink for MonotonicIds {
+    fn shrink(&self) -> Box<Iterator<Item=MonotonicIds>+'static> {
+        Box::new(UniqueIds(self.0.clone())
+                     .shrink()
+                     .filter(|ids| ids.0.windows(2).all(|w| w[0] < w[1]))
+                     .map(|ids| MonotonicIds(ids.0)))
+    }
+}
+
+/// An S-expression: an atom or a parenthesized list of S-expressions.
+///
+/// The `Display` impl renders it in the usual Lisp syntax, which can be
+/// parsed back unambiguously: symbols never contain digits, numbers are
+/// decimal and strings are quoted with `"` and `\` escaped by a `\`.
+///
+/// Generated trees are at most as deep as the depth of the generator, and
+/// every list splits the size of the generator among its elements, so
+/// generation terminates no matter how the random choices turn out.
+///
+/// Shrinking first replaces a list with one of its elements, which makes
+/// the tree shallower, and then shrinks the list itself.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SExpr {
+    Symbol(String),
+    Number(i64),
+    Str(String),
+    List(Vec<SExpr>),
+}
+
+const SYMBOL_CHARS: &'static [char] = &[
+    'a', 'b', 'c', 'f', 'x', 'y', 'z', '+', '-', '*', '/', '<', '=', '?',
+];
+
+impl Arbitrary for SExpr {
+    fn arbitrary<G: Gen>(g: &mut G) -> SExpr {
+        let (size, depth) = (g.size(), g.depth());
+        if size <= 1 || depth == 0 || g.gen_weighted_bool(3) {
+            return match g.gen_range(0, 3) {
+                0 => {
+                    let len = g.gen_range(1, 5);
+                    SExpr::Symbol((0..len).map(|_| *g.choose(SYMBOL_CHARS)
+                                                     .unwrap())
+                                          .collect())
+                }
+                1 => SExpr::Number(Arbitrary::arbitrary(g)),
+                _ => {
+                    const CHARS: &'static [char] =
+                        &['a', 'z', '0', ' ', '(', ')', '"', '\\', '\n'];
+                    let len = g.gen_range(0, cmp::min(size, 8) + 1);
+                    SExpr::Str((0..len).map(|_| *g.choose(CHARS).unwrap())
+                                       .collect())
+                }
+            };
+        }
+        let len = g.gen_range(0, cmp::min(size, 8) + 1);
+        // Every element gets a smaller size than the list, but not zero,
+        // since some atoms can't be generated with size zero.
+        let child_size = cmp::max((size - 1) / cmp::max(len, 1), 1);
+        g.with_depth(depth - 1, |g| {
+            g.with_size(child_size, |g| {
+                SExpr::List((0..len).map(|_| Arbitrary::arbitrary(g))
+                                    .collect())
+            })
+        })
+    }
+}
+
+impl Shrink for SExpr {
+    fn shrink(&self) -> Box<Iterator<Item=SExpr>+'static> {
+        match *self {
+            SExpr::Symbol(ref s) => {
+                let s = s.clone();
+                Box::new((1..s.len()).map(move |n| {
+                    SExpr::Symbol(s[..n].to_string())
+                }))
+            }
+            SExpr::Number(n) => Box::new(n.shrink().map(SExpr::Number)),
+            SExpr::Str(ref s) => Box::new(s.shrink().map(SExpr::Str)),
+            SExpr::List(ref xs) => {
+                Box::new(xs.clone().into_iter()
+                           .chain(xs.shrink().map(SExpr::List)))
+            }
+        }
+    }
+}
+
+impl fmt::Display for SExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SExpr::Symbol(ref s) => write!(f, "{}", s),
+            SExpr::Number(n) => write!(f, "{}", n),
+            SExpr::Str(ref s) => {
+                try!(write!(f, "\""));
+                for c in s.chars() {
+                    if c == '"' || c == '\\' {
+                        try!(write!(f, "\\"));
+                    }
+                    try!(write!(f, "{}", c));
+                }
+                write!(f, "\"")
+            }
+            SExpr::List(ref xs) => {
+                try!(write!(f, "("));
+                for (i, x) in xs.iter().enumerate() {
+                    if i > 0 {
+                        try!(write!(f, " "));
+                    }
+                    try!(write!(f, "{}", x));
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand;
+    use std::cmp;
+    use std::collections::HashSet;
+    use std::iter::Peekable;
+    use super::super::{Arbitrary, Gen, Shrink, StdGen};
+    use super::{
+        ArithCase, ArithOp, ArithOutcome,
+        Base64String,
+        BitSet,
+        BoundedNested,
+        ByteBoundedString,
+        ConfigMap, KeyShape, shrink_config_key,
+        FramedMessage,
+        HexString,
+        Intervals,
+        JsonPointer,
+        Matrix,
+        MonotonicIds,
+        Partitioned,
+        RotatableDeque,
+        RunCollapsingVec,
+        SExpr,
+        Simplex,
+        UnicodeString,
+        UniqueIds,
+        VecFromPool,
+        WrappingOps,
+    };
+
+    fn gen() -> StdGen<rand::ThreadRng> {
+        StdGen::new(rand::thread_rng(), 100)
+    }
+
+    #[test]
+    fn arith_case_labels() {
+        let mut g = gen();
+        let (mut overflows, mut div_zeros) = (0, 0);
+        for _ in 0..1000 {
+            let c: ArithCase<i8> = Arbitrary::arbitrary(&mut g);
+            let expected = match c.op {
+                ArithOp::Div|ArithOp::Rem if c.b == 0 => {
+                    ArithOutcome::DivByZero
+                }
+                ArithOp::Add => overflowed(c.a.overflowing_add(c.b)),
+                ArithOp::Sub => overflowed(c.a.overflowing_sub(c.b)),
+                ArithOp::Mul => overflowed(c.a.overflowing_mul(c.b)),
+                ArithOp::Div => overflowed(c.a.overflowing_div(c.b)),
+                ArithOp::Rem => overflowed(c.a.overflowing_rem(c.b)),
+            };
+            assert_eq!(c.outcome, expected);
+            match c.outcome {
+                ArithOutcome::Overflow => overflows += 1,
+                ArithOutcome::DivByZero => div_zeros += 1,
+                ArithOutcome::InRange => {}
+            }
+        }
+        assert!(overflows > 0);
+        assert!(div_zeros > 0);
+    }
+
+    #[test]
+    fn arith_case_shrink_keeps_outcome() {
+        let mut g = gen();
+        for _ in 0..1000 {
+            let c: ArithCase<u8> = Arbitrary::arbitrary(&mut g);
+            for s in c.shrink() {
+                assert_eq!(s.op, c.op);
+                assert_eq!(s.outcome, c.outcome);
+                assert_eq!(s.checked().is_some(),
+                           s.outcome == ArithOutcome::InRange);
+            }
+        }
+    }
+
+    #[test]
+    fn rotatable_deques() {
+        let deque = |xs: Vec<u8>| RotatableDeque(xs.into_iter().collect());
+
+        // The canonical rotation is tried first.
+        let shrunk: Vec<_> = deque(vec![3, 1, 2]).shrink().collect();
+        assert_eq!(shrunk[0], deque(vec![1, 2, 3]));
+
+        // A deque that is already canonical is never rotated again.
+        let canonical = deque(vec![1, 2, 3]);
+        assert_eq!(canonical.shrink().next(), Some(deque(vec![])));
+
+        let mut d = deque(vec![3, 1, 2]);
+        while let Some(sd) = d.shrink().next() {
+            d = sd;
+        }
+        assert_eq!(d, deque(vec![]));
+    }
+
+    #[test]
+    fn json_pointers() {
+        let mut g = gen();
+        let (mut valid, mut invalid) = (0, 0);
+        for _ in 0..1000 {
+            let p: JsonPointer = Arbitrary::arbitrary(&mut g);
+            match decode_json_pointer(&p.pointer) {
+                Some(tokens) => {
+                    assert!(p.valid, "{:?} should be valid", p);
+                    assert_eq!(encode_json_pointer(&tokens), p.pointer);
+                    valid += 1;
+                }
+                None => {
+                    assert!(!p.valid, "{:?} should be invalid", p);
+                    invalid += 1;
+                }
+            }
+            for sp in p.shrink() {
+                assert_eq!(sp.valid, p.valid);
+                assert_eq!(decode_json_pointer(&sp.pointer).is_some(),
+                           sp.valid);
+            }
+        }
+        assert!(valid > 0);
+        assert!(invalid > 0);
+    }
+
+    #[test]
+    fn base64_strings() {
+        let mut g = gen();
+        let (mut valid, mut invalid) = (0, 0);
+        for i in 0..1000 {
+            let b: Base64String = Arbitrary::arbitrary(&mut g);
+            assert_eq!(decode_base64(&b.encoded).is_some(), b.valid,
+                       "{:?}", b);
+            if b.valid { valid += 1 } else { invalid += 1 }
+            // Checking every shrink of every string takes a while.
+            if i >= 100 {
+                continue;
+            }
+            for sb in b.shrink() {
+                assert_eq!(sb.valid, b.valid);
+                assert_eq!(decode_base64(&sb.encoded).is_some(), sb.valid);
+                assert!(sb.encoded.len() < b.encoded.len());
+            }
+        }
+        assert!(valid > 0);
+        assert!(invalid > 0);
+
+        for &(s, valid) in &[("", true), ("Zg==", true), ("Zm8=", true),
+                             ("Zm9vYmFy", true), ("Zg=", false),
+                             ("Zh==", false), ("Zm9=", false),
+                             ("Z===", false), ("Zg==Zg==", false),
+                             ("Zm9v!A==", false)] {
+            assert_eq!(Base64String::new(s.to_string()).valid, valid, "{}", s);
+        }
+        assert_eq!(decode_base64("Zm9vYmE="), Some(b"fooba".to_vec()));
+    }
+
+    #[test]
+    fn hex_strings() {
+        let mut g = gen();
+        let (mut valid, mut invalid) = (0, 0);
+        for i in 0..1000 {
+            let h: HexString = Arbitrary::arbitrary(&mut g);
+            assert_eq!(decode_hex(&h.encoded).is_some(), h.valid, "{:?}", h);
+            if h.valid { valid += 1 } else { invalid += 1 }
+            if i >= 100 {
+                continue;
+            }
+            for sh in h.shrink() {
+                assert_eq!(sh.valid, h.valid);
+                assert_eq!(decode_hex(&sh.encoded).is_some(), sh.valid);
+                assert!(sh.encoded.len() < h.encoded.len());
+            }
+        }
+        assert!(valid > 0);
+        assert!(invalid > 0);
+    }
+
+    fn decode_base64(s: &str) -> Option<Vec<u8>> {
+        const ALPHABET: &'static str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                                         abcdefghijklmnopqrstuvwxyz\
+                                         0123456789+/";
+        let data = s.trim_right_matches('=');
+        if s.len() % 4 != 0 || s.len() - data.len() > 2 {
+            return None;
+        }
+        let (mut bytes, mut acc, mut bits) = (vec![], 0u32, 0);
+        for c in data.chars() {
+            let v = match ALPHABET.find(c) {
+                Some(v) => v as u32,
+                None => return None,
+            };
+            acc = acc << 6 | v;
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((acc >> bits) as u8);
+                acc &= (1 << bits) - 1;
+            }
+        }
+        if acc != 0 { None } else { Some(bytes) }
+    }
+
+    fn decode_hex(s: &str) -> Option<Vec<u8>> {
+        let digits: Vec<u32> = s.chars().filter_map(|c| c.to_digit(16))
+                                        .collect();
+        if digits.len() != s.len() || digits.len() % 2 != 0 {
+            return None;
+        }
+        Some(digits.chunks(2).map(|d| (d[0] * 16 + d[1]) as u8).collect())
+    }
+
+    fn decode_json_pointer(s: &str) -> Option<Vec<String>> {
+        if s.is_empty() {
+            return Some(vec![]);
+        }
+        if !s.starts_with('/') {
+            return None;
+        }
+        let mut tokens = vec![];
+        for raw in s[1..].split('/') {
+            let mut token = String::new();
+            let mut chars = raw.chars();
+            while let Some(c) = chars.next() {
+                if c != '~' {
+                    token.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('0') => token.push('~'),
+                    Some('1') => token.push('/'),
+                    _ => return None,
+                }
+            }
+            tokens.push(token);
+        }
+        Some(tokens)
+    }
+
+    fn encode_json_pointer(tokens: &[String]) -> String {
+        tokens.iter()
+              .map(|t| format!("/{}", t.replace("~", "~0").replace("/", "~1")))
+              .collect()
+    }
+
+    #[test]
+    fn vec_from_pool_duplicates() {
+        type Elem = (u32, u32, u32);
+        fn has_dup(xs: &[Elem]) -> bool {
+            xs.iter().enumerate().any(|(i, x)| xs[..i].contains(x))
+        }
+
+        let mut g = gen();
+        let (mut pooled, mut plain) = (0, 0);
+        for _ in 0..500 {
+            let xs: VecFromPool<Elem> = Arbitrary::arbitrary(&mut g);
+            let ys: Vec<Elem> = Arbitrary::arbitrary(&mut g);
+            if has_dup(&xs.0) { pooled += 1; }
+            if has_dup(&ys) { plain += 1; }
+        }
+        assert!(pooled > 400, "only {} of 500 had duplicates", pooled);
+        assert!(pooled > 10 * plain);
+    }
+
+    #[test]
+    fn vec_from_pool_shrinks() {
+        let shrunk: Vec<_> = VecFromPool(vec![2u8, 2, 1]).shrink().collect();
+        assert!(shrunk.contains(&VecFromPool(vec![])));
+        assert!(shrunk.contains(&VecFromPool(vec![2, 1])));
+        assert!(shrunk.contains(&VecFromPool(vec![0, 0, 1])));
+        assert!(shrunk.contains(&VecFromPool(vec![2, 2, 0])));
+        assert!(!shrunk.contains(&VecFromPool(vec![0, 2, 1])));
+    }
+
+    #[test]
+    fn partitioned_chunks_sum_to_len() {
+        fn check(p: &Partitioned<u8>) {
+            assert!(!p.1.is_empty(), "{:?}", p);
+            assert_eq!(p.1.iter().fold(0, |a, &b| a + b), p.0.len(),
+                       "{:?}", p);
+        }
+        let mut g = gen();
+        for _ in 0..200 {
+            let p: Partitioned<u8> = Arbitrary::arbitrary(&mut g);
+            check(&p);
+            for sp in p.shrink() {
+                check(&sp);
+            }
+        }
+    }
+
+    #[test]
+    fn partitioned_shrinks() {
+        let p = Partitioned(vec![1u8, 2, 3], vec![1, 0, 2]);
+        let shrunk: Vec<_> = p.shrink().take(7).collect();
+        assert_eq!(shrunk, vec![
+            Partitioned(vec![], vec![0]),
+            Partitioned(vec![2, 3], vec![0, 2]),
+            Partitioned(vec![1], vec![1, 0]),
+            Partitioned(vec![1, 2, 3], vec![1, 2]),
+            Partitioned(vec![2, 3], vec![0, 0, 2]),
+            Partitioned(vec![1, 2], vec![1, 0, 1]),
+            Partitioned(vec![0, 2, 3], vec![1, 0, 2]),
+        ]);
+        // All three merges of `[1, 0, 0, 1]` give `[1, 0, 1]`.
+        let p = Partitioned(vec![1u8, 2], vec![1, 0, 0, 1]);
+        let merged: Vec<_> = p.shrink().filter(|q| q.0 == p.0).collect();
+        assert_eq!(merged, vec![Partitioned(vec![1, 2], vec![1, 0, 1])]);
+    }
+
+    #[test]
+    fn unique_ids() {
+        fn unique(ids: &[u64]) -> bool {
+            ids.iter().collect::<HashSet<_>>().len() == ids.len()
+        }
+        let mut g = gen();
+        let mut longest = 0;
+        for _ in 0..200 {
+            let ids: UniqueIds = Arbitrary::arbitrary(&mut g);
+            assert!(unique(&ids.0), "{:?}", ids);
+            longest = cmp::max(longest, ids.0.len());
+            for sids in ids.shrink() {
+                assert!(unique(&sids.0), "{:?} shrunk to {:?}", ids, sids);
+            }
+
+            let ids: MonotonicIds = Arbitrary::arbitrary(&mut g);
+            assert!(ids.0.windows(2).all(|w| w[0] < w[1]), "{:?}", ids);
+            for sids in ids.shrink() {
+                assert!(sids.0.windows(2).all(|w| w[0] < w[1]),
+                        "{:?} shrunk to {:?}", ids, sids);
+            }
+        }
+        assert!(longest > 50);
+
+        let shrunk: Vec<_> = UniqueIds(vec![1, 0]).shrink().collect();
+        assert_eq!(shrunk, vec![UniqueIds(vec![]), UniqueIds(vec![0]),
+                                UniqueIds(vec![1])]);
+        let shrunk: Vec<_> = MonotonicIds(vec![0, 2]).shrink().collect();
+        assert_eq!(shrunk, vec![
+            MonotonicIds(vec![]), MonotonicIds(vec![2]),
+            MonotonicIds(vec![0]), MonotonicIds(vec![0, 1]),
+        ]);
+    }
+
+    #[test]
+    fn run_collapsing_vecs() {
+        let xs = RunCollapsingVec(vec![1, 1, 1, 2, 2]);
+        assert_eq!(xs.shrink().next(), Some(RunCollapsingVec(vec![1, 2])));
+        assert_eq!(xs.shrink().count(), xs.0.shrink().count() + 1);
+
+        let ys = RunCollapsingVec(vec![1, 2, 1]);
+        let shrunk: Vec<_> = ys.shrink().map(|v| v.0).collect();
+        assert_eq!(shrunk, ys.0.shrink().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn framed_messages() {
+        fn check(m: &FramedMessage) {
+            assert_eq!(m.valid, m.length as usize == m.payload.len());
+            let bytes = m.to_bytes();
+            assert_eq!(bytes.len(), 5 + m.payload.len());
+            let length = bytes[1..5].iter()
+                                    .fold(0u32, |n, &b| (n << 8) | b as u32);
+            assert_eq!(length, m.length);
+        }
+
+        let mut g = gen();
+        let (mut valid, mut corrupt) = (0, 0);
+        for _ in 0..500 {
+            let m: FramedMessage = Arbitrary::arbitrary(&mut g);
+            check(&m);
+            if m.valid { valid += 1 } else { corrupt += 1 }
+            for sm in m.shrink() {
+                check(&sm);
+                assert_eq!(sm.valid, m.valid);
+            }
+        }
+        assert!(valid > 0);
+        assert!(corrupt > 0);
+    }
+
+    #[test]
+    fn intervals_are_valid() {
+        let mut g = gen();
+        for _ in 0..200 {
+            let ivs: Intervals<i32> = Arbitrary::arbitrary(&mut g);
+            assert!(ivs.intervals.iter().all(|&(s, e)| s <= e));
+            for sivs in ivs.shrink() {
+                assert!(sivs.intervals.iter().all(|&(s, e)| s <= e));
+            }
+        }
+    }
+
+    #[test]
+    fn intervals_density() {
+        let mut g = gen();
+        let (mut sparse, mut dense) = (0, 0);
+        for _ in 0..100 {
+            sparse += Intervals::<u32>::with_density(&mut g, 0.0).overlaps;
+            dense += Intervals::<u32>::with_density(&mut g, 1.0).overlaps;
+        }
+        assert!(dense > 10 * sparse, "{} vs. {}", dense, sparse);
+    }
+
+    #[test]
+    fn intervals_extreme_bounds() {
+        use std::i64;
+
+        let ivs = Intervals::<i64>::new(vec![(i64::MIN, i64::MAX), (-1, 1)]);
+        assert_eq!(ivs.overlaps, 1);
+        for sivs in ivs.shrink() {
+            assert!(sivs.intervals.iter().all(|&(s, e)| s <= e));
+        }
+        let whole = vec![(i64::MIN, i64::MAX)];
+        assert!(ivs.shrink().any(|s| s.intervals == whole));
+    }
+
+    #[test]
+    fn intervals_overlaps() {
+        let ivs = Intervals::<u32>::new(vec![(0, 5), (3, 8), (8, 9), (4, 4)]);
+        assert_eq!(ivs.overlaps, 1);
+    }
+
+    #[test]
+    fn matrices_are_rectangular() {
+        fn rectangular(m: &Matrix<u8>) -> bool {
+            m.data.len() == m.rows
+            && m.data.iter().all(|row| row.len() == m.cols)
+        }
+        let mut g = gen();
+        for _ in 0..50 {
+            let m: Matrix<u8> = Arbitrary::arbitrary(&mut g);
+            assert!(rectangular(&m), "{:?}", m);
+            for sm in m.shrink() {
+                assert!(rectangular(&sm), "{:?}", sm);
+            }
+        }
+    }
+
+    #[test]
+    fn matrix_shrinks_rows_and_columns() {
+        let m = Matrix { rows: 2, cols: 3, data: vec![vec![0u8; 3]; 2] };
+        let dims: Vec<(usize, usize)> =
+            m.shrink().map(|sm| (sm.rows, sm.cols)).collect();
+        assert!(dims.contains(&(1, 3)));
+        assert!(dims.contains(&(2, 2)));
+        assert!(dims.contains(&(0, 3)));
+        assert!(dims.contains(&(2, 0)));
+    }
+
+    #[test]
+    fn config_map_keys_have_shapes() {
+        let mut g = gen();
+        let mut shapes = HashSet::new();
+        for _ in 0..20 {
+            let m: ConfigMap = Arbitrary::arbitrary(&mut g);
+            for k in m.0.keys() {
+                shapes.insert(KeyShape::of(k).expect(k));
+            }
+            for sm in m.shrink().take(100) {
+                for k in sm.0.keys() {
+                    assert!(KeyShape::of(k).is_some(), "bad key {}", k);
+                }
+            }
+        }
+        assert_eq!(shapes.len(), 4);
+    }
+
+    #[test]
+    fn config_key_shapes() {
+        assert_eq!(KeyShape::of("DATABASE_URL"), Some(KeyShape::UpperSnake));
+        assert_eq!(KeyShape::of("server.http2.port"),
+                   Some(KeyShape::Dotted));
+        assert_eq!(KeyShape::of("max-connections"), Some(KeyShape::Kebab));
+        assert_eq!(KeyShape::of("port"), Some(KeyShape::Word));
+        assert_eq!(KeyShape::of("http2"), Some(KeyShape::Word));
+        assert_eq!(KeyShape::of("PORT"), Some(KeyShape::UpperSnake));
+        for key in ["", "a..b", "a.b-c", "Mixed_Case", "2fast", "é"].iter() {
+            assert_eq!(KeyShape::of(key), None);
+        }
+    }
+
+    #[test]
+    fn config_key_shrinks_keep_shape() {
+        let m = ConfigMap(vec![("LOG_LEVEL".to_string(), "".to_string())]
+                              .into_iter().collect());
+        let keys: HashSet<String> = m.shrink()
+            .filter(|sm| sm.0.len() == 1)
+            .flat_map(|sm| sm.0.into_iter().map(|(k, _)| k))
+            .collect();
+        for key in ["LOG", "LEVEL", "L_LEVEL", "LO_LEVEL", "LOG_L", "LOG_LE",
+                    "LOG_LEVE"].iter() {
+            assert!(keys.contains(*key), "missing {}", key);
+        }
+        assert!(keys.iter().all(|k| {
+            KeyShape::of(k) == Some(KeyShape::UpperSnake)
+        }));
+    }
+
+    #[test]
+    fn config_key_shrinks_to_word() {
+        let keys = shrink_config_key("max-conns");
+        assert!(keys.contains(&"max".to_string()), "{:?}", keys);
+        for k in &keys {
+            let shape = KeyShape::of(k).unwrap();
+            let words = k.split('-').count();
+            assert_eq!(shape == KeyShape::Word, words == 1, "{}", k);
+        }
+    }
+
+    #[test]
+    fn bit_set_regimes() {
+        let mut g = gen();
+        let (mut empty, mut sparse, mut mid, mut dense, mut full) =
+            (0, 0, 0, 0, 0);
+        for _ in 0..500 {
+            let bs: BitSet = Arbitrary::arbitrary(&mut g);
+            if bs.0.is_empty() {
+                continue;
+            }
+            match bs.density() {
+                d if d == 0.0 => empty += 1,
+                d if d < 0.2 => sparse += 1,
+                d if d > 0.4 && d < 0.6 => mid += 1,
+                d if d == 1.0 => full += 1,
+                d if d > 0.8 => dense += 1,
+                _ => {}
+            }
+        }
+        for &n in [empty, sparse, mid, dense, full].iter() {
+            assert!(n > 20, "{:?}", (empty, sparse, mid, dense, full));
+        }
+    }
+
+    #[test]
+    fn bit_set_shrinks_clear_bits() {
+        let bs = BitSet(vec![0b101, !0]);
+        let shrunk: Vec<BitSet> = bs.shrink().collect();
+        assert_eq!(shrunk[0], BitSet(vec![0, 0]));
+        assert!(shrunk.contains(&BitSet(vec![0, !0])));
+        assert!(shrunk.contains(&BitSet(vec![0b101, 0])));
+        assert!(shrunk.contains(&BitSet(vec![0b100, !0])));
+        assert_eq!(shrunk.len(), 1 + 2 + 66);
+        for s in shrunk.iter() {
+            assert!(s.count_ones() < bs.count_ones());
+        }
+        assert_eq!(BitSet(vec![0, 0]).shrink().count(), 0);
+    }
+
+    #[test]
+    fn simplexes() {
+        fn assert_simplex(s: &Simplex) {
+            let sum = s.0.iter().fold(0.0, |a, &b| a + b);
+            assert!(!s.0.is_empty());
+            assert!((sum - 1.0).abs() < 1e-9, "{:?} sums to {}", s, sum);
+            assert!(s.0.iter().all(|&p| p >= 0.0), "{:?}", s);
+        }
+        let mut g = gen();
+        for _ in 0..100 {
+            let s: Simplex = Arbitrary::arbitrary(&mut g);
+            assert_simplex(&s);
+            for ss in s.shrink() {
+                assert_simplex(&ss);
+            }
+        }
+    }
+
+    #[test]
+    fn simplex_shrinks_to_single_category() {
+        let mut s = Simplex(vec![0.5, 0.0, 0.25, 0.25]);
+        while let Some(ss) = s.shrink().last() {
+            s = ss;
+        }
+        assert_eq!(s, Simplex(vec![1.0]));
+    }
+
+    #[test]
+    fn wrapping_ops_apply() {
+        use std::num::Wrapping;
+
+        let ops = WrappingOps(vec![
+            (ArithOp::Add, 200u8), (ArithOp::Mul, 2), (ArithOp::Sub, 150),
+        ]);
+        assert_eq!(ops.apply(Wrapping(100)), Wrapping(194));
+    }
+
+    #[test]
+    fn wrapping_ops_shrink() {
+        use std::num::Wrapping;
+
+        // Adding and multiplying is order dependent, so this fails on
+        // most sequences. The minimal sequences that make it fail have
+        // two steps.
+        fn reversible(ops: &WrappingOps<u8>) -> bool {
+            let mut reversed = ops.clone();
+            reversed.0.reverse();
+            ops.apply(Wrapping(1)) == reversed.apply(Wrapping(1))
+        }
+        let mut g = gen();
+        let mut ops: WrappingOps<u8> = Arbitrary::arbitrary(&mut g);
+        while reversible(&ops) {
+            ops = Arbitrary::arbitrary(&mut g);
+        }
+        while let Some(smaller) = ops.shrink().find(|o| !reversible(o)) {
+            ops = smaller;
+        }
+        assert_eq!(ops.0.len(), 2);
+        assert_eq!(ops.0.iter().filter(|s| s.0 == ArithOp::Mul).count(), 1);
+    }
+
+    #[test]
+    fn bounded_nested_maps() {
+        let mut g = gen();
+        let mut largest = 0;
+        for _ in 0..100 {
+            let m: BoundedNested<u8, u8> =
+                BoundedNested::with_budget(&mut g, 50);
+            assert!(m.elements() <= 50, "{} elements", m.elements());
+            largest = cmp::max(largest, m.elements());
+            for sm in m.shrink().take(100) {
+                assert!(sm.elements() <= m.elements());
+            }
+        }
+        assert!(largest > 25);
+        for _ in 0..100 {
+            let m: BoundedNested<u8, Vec<u8>> = Arbitrary::arbitrary(&mut g);
+            assert!(m.elements() <= 100);
+        }
+    }
+
+    #[test]
+    fn byte_bounded_strings() {
+        let mut g = gen();
+        let mut widths = HashSet::new();
+        for budget in 0..50 {
+            for _ in 0..20 {
+                let s = ByteBoundedString::with_budget(&mut g, budget);
+                assert!(s.0.len() <= budget, "{:?} over {}", s, budget);
+                widths.extend(s.0.chars().map(|c| c.len_utf8()));
+                for ss in s.shrink() {
+                    assert!(ss.0.len() <= s.0.len());
+                }
+            }
+        }
+        assert_eq!(widths.len(), 4);
+        for _ in 0..100 {
+            let s: ByteBoundedString = Arbitrary::arbitrary(&mut g);
+            assert!(s.0.len() <= 100);
+        }
+    }
+
+    #[test]
+    fn unicode_strings() {
+        let mut g = gen();
+        let mut widths = HashSet::new();
+        for _ in 0..100 {
+            let s: UnicodeString = Arbitrary::arbitrary(&mut g);
+            assert!(s.0.chars().count() < 100);
+            widths.extend(s.0.chars().map(|c| c.len_utf8()));
+            let mut end = 0;
+            for (i, c) in s.0.char_indices() {
+                assert_eq!(i, end);
+                assert!(s.0.is_char_boundary(i));
+                end += c.len_utf8();
+            }
+            assert_eq!(end, s.0.len());
+        }
+        assert_eq!(widths.len(), 4);
+
+        let s = UnicodeString("\u{1F600}\u{E9}".to_string());
+        for ss in s.shrink().take(100) {
+            assert!(ss.0.chars().count() <= 2, "{:?}", ss);
+        }
+    }
+
+    fn overflowed<T>((_, o): (T, bool)) -> ArithOutcome {
+        if o { ArithOutcome::Overflow } else { ArithOutcome::InRange }
+    }
+
+    #[test]
+    fn sexprs_round_trip() {
+        let mut g = gen();
+        for _ in 0..1000 {
+            let e: SExpr = Arbitrary::arbitrary(&mut g);
+            assert_eq!(parse_sexpr(&e.to_string()), Some(e.clone()));
+            for shrunk in e.shrink().take(20) {
+                assert_eq!(parse_sexpr(&shrunk.to_string()), Some(shrunk));
+            }
+        }
+    }
+
+    #[test]
+    fn sexprs_bounded() {
+        fn depth(e: &SExpr) -> usize {
+            match *e {
+                SExpr::List(ref xs) => {
+                    1 + xs.iter().map(depth).max().unwrap_or(0)
+                }
+                _ => 0,
+            }
+        }
+        fn nodes(e: &SExpr) -> usize {
+            match *e {
+                SExpr::List(ref xs) => {
+                    xs.iter().fold(1, |n, x| n + nodes(x))
+                }
+                _ => 1,
+            }
+        }
+        let mut g = StdGen::new(rand::thread_rng(), 10000);
+        for _ in 0..100 {
+            let e: SExpr = g.with_depth(4, |g| Arbitrary::arbitrary(g));
+            assert!(depth(&e) <= 4, "{}", e);
+        }
+        // Even without a depth limit, the size limits the whole tree.
+        for _ in 0..100 {
+            let e: SExpr = Arbitrary::arbitrary(&mut g);
+            assert!(nodes(&e) <= 3 * 10000, "{}", e);
+        }
+    }
+
+    #[test]
+    fn sexprs_shrink_lists_to_elements_first() {
+        let e = SExpr::List(vec![
+            SExpr::Number(5),
+            SExpr::List(vec![SExpr::Symbol("ab".to_string())]),
+        ]);
+        let shrinks: Vec<SExpr> = e.shrink().take(2).collect();
+        assert_eq!(shrinks, vec![
+            SExpr::Number(5),
+            SExpr::List(vec![SExpr::Symbol("ab".to_string())]),
+        ]);
+    }
+
+    fn parse_sexpr(s: &str) -> Option<SExpr> {
+        let mut chars = s.chars().peekable();
+        let e = parse_sexpr_from(&mut chars);
+        if chars.next().is_some() { None } else { e }
+    }
+
+    fn parse_sexpr_from<I>(chars: &mut Peekable<I>) -> Option<SExpr>
+            where I: Iterator<Item=char> {
+        match chars.next() {
+            Some('(') => {
+                let mut xs = vec![];
+                loop {
+                    match chars.peek() {
+                        Some(&')') => {
+                            chars.next();
+                            return Some(SExpr::List(xs));
+                        }
+                        Some(&' ') if !xs.is_empty() => { chars.next(); }
+                        Some(_) if xs.is_empty() => {}
+                        _ => return None,
+                    }
+                    match parse_sexpr_from(chars) {
+                        Some(x) => xs.push(x),
+                        None => return None,
+                    }
+                }
+            }
+            Some('"') => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => return Some(SExpr::Str(s)),
+                        Some('\\') => match chars.next() {
+                            Some(c) => s.push(c),
+                            None => return None,
+                        },
+                        Some(c) => s.push(c),
+                        None => return None,
+                    }
+                }
+            }
+            Some(c) if c != ')' && c != ' ' => {
+                let mut token = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c == ' ' || c == '(' || c == ')' {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                Some(match token.parse() {
+                    Ok(n) => SExpr::Number(n),
+                    Err(_) => SExpr::Symbol(token),
+                })
+            }
+            _ => None,
+        }
+    }
+}
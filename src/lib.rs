@@ -9,18 +9,77 @@
 
 #![cfg_attr(test, feature(core, step_by))]
 
+#[cfg(feature = "bytes")]
+extern crate bytes;
 #[cfg(feature = "collect_impls")]
 extern crate collect;
 #[macro_use] extern crate log;
 extern crate rand;
+#[cfg(feature = "rust_decimal")]
+extern crate rust_decimal;
+#[cfg(feature = "semver")]
+extern crate semver;
 
 pub use arbitrary::{
-    Arbitrary, Gen, StdGen,
-    empty_shrinker, single_shrinker,
+    Arbitrary, Gen, SplitMix64, StdGen,
+    Ascii, AsciiString, Bounded, ExtremeFloat, ExtremeFloat32, Fixed,
+    Fixtures, FullRange, LogUniform, Negative, NoShrink, NonEmptyVec,
+    NonNegative, NoneBiased, Positive, RawBytes, Shrinker,
+    SomeBiased, Sorted, Unique,
+    base_value, choose_variant, empty_domain, empty_shrinker,
+    filter_shrinker, map_shrinker, resize, shrink_signed, shrink_toward,
+    shrink_unsigned, single_shrinker, variant_weight,
 };
-pub use tester::{QuickCheck, Testable, TestResult, quickcheck};
+pub use generators::{
+    ArithCase, ArithOp, ArithOutcome,
+    Base64String,
+    BitSet,
+    BoundedNested,
+    ByteBoundedString,
+    ConfigMap, KeyShape,
+    FramedMessage,
+    HexString,
+    Intervals,
+    JsonPointer,
+    Matrix,
+    MonotonicIds,
+    Partitioned,
+    RotatableDeque,
+    RunCollapsingVec,
+    SExpr,
+    Simplex,
+    UnicodeString,
+    UniqueIds,
+    VecFromPool,
+    WrappingOps,
+};
+pub use shrink::{Shrink, minimize};
+pub use state_machine::StateMachine;
+pub use tester::{QuickCheck, RunReport, Testable, TestResult, quickcheck};
+
+/// Re-exports the items that most property tests need.
+///
+/// ```rust
+/// use quickcheck::prelude::*;
+///
+/// fn prop(xs: Vec<u8>) -> TestResult {
+///     TestResult::from_bool(xs.len() == xs.iter().count())
+/// }
+/// quickcheck(prop as fn(Vec<u8>) -> TestResult);
+/// ```
+pub mod prelude {
+    pub use arbitrary::{
+        Arbitrary, Gen, StdGen,
+        empty_shrinker, single_shrinker,
+    };
+    pub use shrink::{Shrink, minimize};
+    pub use tester::{QuickCheck, Testable, TestResult, quickcheck};
+}
 
 mod arbitrary;
+mod generators;
+mod shrink;
+mod state_machine;
 mod tester;
 
 #[cfg(test)]
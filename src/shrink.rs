@@ -0,0 +1,95 @@
+use super::empty_shrinker;
+
+/// `Shrink` describes types whose values can be shrunk, whether or not they
+/// can be randomly generated.
+///
+/// This makes it possible to minimize a value that wasn't generated by
+/// QuickCheck (e.g., a counterexample found some other way) without
+/// implementing `Arbitrary`, which requires `Clone + Send`.
+///
+/// `Arbitrary` requires `Shrink`, and the standard library types implement
+/// it independently of `Arbitrary`: e.g., an `Option<A>` can be shrunk
+/// whenever `A` can, even if `A` isn't `Clone` or `Send`. Since shrunk
+/// values are returned by a `'static` iterator, only `'static` types can
+/// implement `Shrink`.
+pub trait Shrink : Sized + 'static {
+    /// Returns values that are smaller than `self`, simplest first.
+    ///
+    /// By default, nothing is returned.
+    fn shrink(&self) -> Box<Iterator<Item=Self>+'static> {
+        empty_shrinker()
+    }
+}
+
+/// Shrinks `value` for as long as a shrunk value still `fails`, and returns
+/// the last value that did.
+///
+/// At each step, the first shrunk value that fails is kept, like QuickCheck
+/// does with the arguments of a failing property. If `value` itself doesn't
+/// fail, it is returned unchanged.
+pub fn minimize<A, F>(value: A, mut fails: F) -> A
+        where A: Shrink, F: FnMut(&A) -> bool {
+    let mut value = value;
+    while let Some(smaller) = Shrink::shrink(&value).find(|v| fails(v)) {
+        value = smaller;
+    }
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::{Shrink, minimize};
+
+    // Not `Clone`, so it can't implement `Arbitrary`.
+    #[derive(Debug, PartialEq)]
+    struct Bits(Vec<bool>);
+
+    impl Shrink for Bits {
+        fn shrink(&self) -> Box<Iterator<Item=Bits>+'static> {
+            Box::new(self.0.shrink().map(Bits))
+        }
+    }
+
+    #[test]
+    fn standalone_shrink() {
+        let bits = Bits(vec![true, false]);
+        let shrunk: Vec<Bits> = bits.shrink().collect();
+        assert!(shrunk.contains(&Bits(vec![])));
+        assert!(shrunk.contains(&Bits(vec![true])));
+        assert!(shrunk.contains(&Bits(vec![false, false])));
+    }
+
+    #[test]
+    fn minimizes() {
+        let bits = Bits(vec![false, true, true, false, true]);
+        let minimal = minimize(bits, |b| b.0.iter().any(|&x| x));
+        assert_eq!(minimal, Bits(vec![true]));
+
+        assert_eq!(minimize(100usize, |&n| n >= 17), 17);
+        assert_eq!(minimize(5usize, |&n| n > 10), 5);
+    }
+
+    // Neither `Clone` nor `Send`.
+    #[derive(Debug, PartialEq)]
+    struct Shared(Rc<u8>);
+
+    impl Shrink for Shared {
+        fn shrink(&self) -> Box<Iterator<Item=Shared>+'static> {
+            Box::new((*self.0).shrink().map(|n| Shared(Rc::new(n))))
+        }
+    }
+
+    #[test]
+    fn shrink_option_of_non_clone() {
+        let shrunk: Vec<Option<Shared>> =
+            Some(Shared(Rc::new(4))).shrink().collect();
+        assert_eq!(shrunk, vec![None,
+                                Some(Shared(Rc::new(0))),
+                                Some(Shared(Rc::new(2))),
+                                Some(Shared(Rc::new(3)))]);
+        assert_eq!(minimize(Some(Shared(Rc::new(9))), |x| x.is_some()),
+                   Some(Shared(Rc::new(0))));
+    }
+}
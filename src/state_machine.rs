@@ -0,0 +1,122 @@
+use std::fmt::Debug;
+use std::time::Instant;
+
+use super::{Arbitrary, Gen, Shrink};
+
+/// A model of a stateful system, used to test it with random sequences of
+/// commands.
+///
+/// `QuickCheck::state_machine` starts from `initial_state` and applies
+/// commands one at a time, only picking commands whose `precondition`
+/// holds in the current state. The test fails as soon as `invariant`
+/// doesn't hold. The failing sequence of commands is then shrunk, by
+/// removing and shrinking commands, to a shorter sequence that still
+/// satisfies every precondition and still fails.
+///
+/// A typical `State` contains both the system under test and a simple
+/// model of it, and `invariant` checks that the two agree.
+pub trait StateMachine {
+    /// The state that commands are applied to.
+    type State;
+    /// A single operation on the state.
+    type Command: Arbitrary + Debug;
+
+    /// Returns the state before any command is applied.
+    fn initial_state(&self) -> Self::State;
+
+    /// Returns whether `cmd` may be applied to `state`.
+    ///
+    /// By default, every command may be applied in every state.
+    fn precondition(&self, _state: &Self::State, _cmd: &Self::Command)
+                   -> bool {
+        true
+    }
+
+    /// Applies `cmd` to `state`.
+    ///
+    /// This is called on the current thread, so a panic propagates to the
+    /// caller instead of failing the test.
+    fn apply(&self, state: &mut Self::State, cmd: &Self::Command);
+
+    /// Returns whether `state` is correct. This is checked after every
+    /// command.
+    fn invariant(&self, _state: &Self::State) -> bool {
+        true
+    }
+}
+
+/// How many commands to generate for a step before giving up on finding one
+/// whose precondition holds.
+const MAX_COMMAND_TRIES: usize = 100;
+
+/// Generates up to `size` commands whose preconditions hold, applying each
+/// of them, and stops early if the invariant fails.
+///
+/// Returns the commands and whether the invariant failed after the last
+/// one.
+pub fn generate<S, G>(sm: &S, g: &mut G) -> (Vec<S::Command>, bool)
+        where S: StateMachine, G: Gen {
+    let mut state = sm.initial_state();
+    let mut cmds = vec![];
+    let len = { let s = g.size(); g.gen_range(0, s + 1) };
+    for _ in 0..len {
+        let cmd = (0..MAX_COMMAND_TRIES)
+            .map(|_| S::Command::arbitrary(g))
+            .find(|cmd| sm.precondition(&state, cmd));
+        let cmd = match cmd {
+            Some(cmd) => cmd,
+            None => break,
+        };
+        sm.apply(&mut state, &cmd);
+        cmds.push(cmd);
+        if !sm.invariant(&state) {
+            return (cmds, true);
+        }
+    }
+    (cmds, false)
+}
+
+/// Applies `cmds` starting from the initial state.
+///
+/// Returns `None` if the precondition of a command doesn't hold, and
+/// otherwise whether the invariant held after every command.
+pub fn run<S: StateMachine>(sm: &S, cmds: &[S::Command]) -> Option<bool> {
+    let mut state = sm.initial_state();
+    for cmd in cmds {
+        if !sm.precondition(&state, cmd) {
+            return None;
+        }
+        sm.apply(&mut state, cmd);
+        if !sm.invariant(&state) {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+/// Shrinks the failing sequence `cmds` for as long as a shrunk sequence
+/// still satisfies every precondition and still fails, or until `deadline`
+/// passes.
+///
+/// Returns the smallest failing sequence found and the number of
+/// successful shrinks.
+pub fn shrink<S: StateMachine>(sm: &S, mut cmds: Vec<S::Command>,
+                               deadline: Option<Instant>)
+                              -> (Vec<S::Command>, usize) {
+    let past = || deadline.map_or(false, |d| Instant::now() >= d);
+    let mut shrinks = 0;
+    'shrinking: while !past() {
+        for shrunk in cmds.shrink() {
+            if past() {
+                break 'shrinking;
+            }
+            if run(sm, &shrunk) == Some(false) {
+                shrinks += 1;
+                cmds = shrunk;
+                continue 'shrinking;
+            }
+        }
+        break;
+    }
+    (cmds, shrinks)
+}
@@ -1,7 +1,16 @@
 use rand;
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::env;
 use std::fmt::Debug;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::thread;
-use super::{Arbitrary, Gen, StdGen};
+use std::time::{Duration, Instant};
+use super::{Arbitrary, Gen, Shrink, SplitMix64, StateMachine, StdGen};
+use state_machine;
 use tester::trap::safe;
 use tester::Status::{Discard, Fail, Pass};
 
@@ -10,8 +19,30 @@ pub struct QuickCheck<G> {
     tests: usize,
     max_tests: usize,
     gen: G,
+    replay: Vec<u64>,
+    settings: Settings,
 }
 
+/// The configuration that affects how a single test is run.
+#[derive(Clone, Copy, Debug)]
+struct Settings {
+    max_shrink_time: Option<Duration>,
+    deterministic: bool,
+    same_panic: bool,
+    trace_shrink_path: bool,
+}
+
+thread_local!(
+    // The settings of the run in progress on this thread, since
+    // `Testable::result` has no way to pass them to the shrinker.
+    static SETTINGS: Cell<Settings> = Cell::new(Settings {
+        max_shrink_time: None,
+        deterministic: false,
+        same_panic: false,
+        trace_shrink_path: false,
+    })
+);
+
 impl QuickCheck<StdGen<rand::ThreadRng>> {
     /// Creates a new QuickCheck value.
     ///
@@ -22,11 +53,24 @@ impl QuickCheck<StdGen<rand::ThreadRng>> {
     /// By default, the maximum number of passed tests is set to `100`,
     /// the max number of overall tests is set to `10000` and the generator
     /// is set to a `StdGen` with a default size of `100`.
+    ///
+    /// Shrinking has no time limit, unless the environment variable
+    /// `QUICKCHECK_MAX_SHRINK_TIME` is set to a number of milliseconds.
     pub fn new() -> QuickCheck<StdGen<rand::ThreadRng>> {
+        let max_shrink_time = env::var("QUICKCHECK_MAX_SHRINK_TIME").ok()
+            .and_then(|ms| ms.parse().ok())
+            .map(Duration::from_millis);
         QuickCheck {
             tests: 100,
             max_tests: 10000,
             gen: StdGen::new(rand::thread_rng(), 100),
+            replay: vec![],
+            settings: Settings {
+                max_shrink_time: max_shrink_time,
+                deterministic: false,
+                same_panic: false,
+                trace_shrink_path: false,
+            },
         }
     }
 }
@@ -53,12 +97,137 @@ impl<G: Gen> QuickCheck<G> {
         self
     }
 
+    /// Set a limit on how long QuickCheck spends shrinking a failure.
+    ///
+    /// Once the time is up, the smallest failure found so far is reported.
+    /// Note that this only limits shrinking: a property that never returns
+    /// still hangs the test.
+    ///
+    /// This overrides the `QUICKCHECK_MAX_SHRINK_TIME` environment
+    /// variable.
+    pub fn max_shrink_time(mut self, time: Duration) -> QuickCheck<G> {
+        self.settings.max_shrink_time = Some(time);
+        self
+    }
+
+    /// Run every test twice on the same input and fail if the outcomes
+    /// differ.
+    ///
+    /// This catches properties that accidentally depend on global mutable
+    /// state or are otherwise nondeterministic. It only applies to
+    /// properties tested with `run`, `quicktest` and `quickcheck`.
+    pub fn check_deterministic(mut self) -> QuickCheck<G> {
+        self.settings.deterministic = true;
+        self
+    }
+
+    /// When a property fails by panicking, only shrink it to inputs that
+    /// make it panic with the same message.
+    ///
+    /// Without this, shrinking may end up at an input that triggers a
+    /// different bug than the one originally found. Failures that aren't
+    /// panics (or other errors) are shrunk as usual. Like
+    /// `check_deterministic`, this doesn't apply to `run_with_state`.
+    pub fn shrink_same_panic(mut self) -> QuickCheck<G> {
+        self.settings.same_panic = true;
+        self
+    }
+
+    /// Record every failure that shrinking steps through in the
+    /// `shrink_path` of the `RunReport`.
+    ///
+    /// This shows how the original counterexample was reduced to the
+    /// reported one. Unlike the debug log, it only contains the candidates
+    /// that still failed. Like `shrink_same_panic`, this doesn't apply to
+    /// `run_with_state`.
+    pub fn trace_shrink_path(mut self) -> QuickCheck<G> {
+        self.settings.trace_shrink_path = true;
+        self
+    }
+
     /// Set the random number generator to be used by QuickCheck.
+    ///
+    /// The generator only picks the seed of every test. The arguments of
+    /// a test are generated with `StdGen::from_seed(seed, size)`, with the
+    /// size, minimum size, depth, corner values and base values of this
+    /// generator, so that a failure can be reproduced from the seed in its
+    /// `RunReport`.
     pub fn gen(mut self, gen: G) -> QuickCheck<G> {
         self.gen = gen;
         self
     }
 
+    /// Makes `value` the base value of type `T`, which `Fixed<T>` and
+    /// fields with `#[arbitrary(fixed)]` are generated from.
+    ///
+    /// This is ignored if the generator set with `gen` can't hold base
+    /// values. See `StdGen::with_fixture`.
+    pub fn fixture<T>(mut self, value: T) -> QuickCheck<G>
+            where T: Any + Send + Sync {
+        let mut fixtures = self.gen.fixtures().cloned().unwrap_or_default();
+        fixtures.insert(value);
+        self.gen.set_fixtures(fixtures);
+        self
+    }
+
+    /// Replay the seeds saved in every file in the directory `dir` before
+    /// any random tests are run.
+    ///
+    /// Each file should contain one seed (a `u64`) per line. Blank lines
+    /// and lines starting with `#` are ignored. Files that cannot be read,
+    /// contain no seeds or contain anything else are skipped with a
+    /// warning. Seeds that appear more than once are only replayed once.
+    ///
+    /// A seed is replayed by generating the arguments of a property with
+    /// `StdGen::from_seed(seed, size)`, like every other test (see `gen`).
+    /// Replayed tests are run in addition to the number of tests set with
+    /// `tests`.
+    pub fn regressions_dir<P: AsRef<Path>>(mut self, dir: P)
+                                          -> QuickCheck<G> {
+        let dir = dir.as_ref();
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Could not read regressions directory {}: {}",
+                      dir.display(), err);
+                return self;
+            }
+        };
+        let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok())
+                                             .map(|e| e.path())
+                                             .filter(|p| p.is_file())
+                                             .collect();
+        paths.sort();
+        for path in paths {
+            match read_seeds(&path) {
+                Some(seeds) => self.add_seeds(seeds),
+                None => warn!("Skipping malformed or empty regression \
+                               file {}.", path.display()),
+            }
+        }
+        self
+    }
+
+    /// Always run tests with each of the given seeds before any random
+    /// tests are run.
+    ///
+    /// This is meant for a curated list of seeds that once found bugs and
+    /// should keep being tested, regardless of whether they fail now.
+    /// Seeds are replayed the same way as the ones in `regressions_dir`,
+    /// and seeds that appear more than once are only replayed once.
+    pub fn always_seeds(mut self, seeds: Vec<u64>) -> QuickCheck<G> {
+        self.add_seeds(seeds);
+        self
+    }
+
+    fn add_seeds(&mut self, seeds: Vec<u64>) {
+        for seed in seeds {
+            if !self.replay.contains(&seed) {
+                self.replay.push(seed);
+            }
+        }
+    }
+
     /// Tests a property and returns the result.
     ///
     /// The result returned is either the number of tests passed or a witness
@@ -68,32 +237,239 @@ impl<G: Gen> QuickCheck<G> {
     /// want to use the `quickcheck` method, which will `panic!` on failure.)
     pub fn quicktest<A>(&mut self, f: A) -> Result<usize, TestResult>
                     where A: Testable {
+        let report = self.run(f);
+        match report.failure {
+            None => Ok(report.passed),
+            Some(r) => Err(r),
+        }
+    }
+
+    /// Tests a property and returns a report describing the whole run.
+    ///
+    /// Unlike `quicktest`, this includes statistics such as the number of
+    /// discarded tests and how long the run took, which is useful when
+    /// results need to be recorded rather than just checked.
+    pub fn run<A>(&mut self, f: A) -> RunReport where A: Testable {
+        self.run_tests(|g| f.result(g))
+    }
+
+    /// Tests a property that needs access to mutable state and returns a
+    /// report describing the whole run.
+    ///
+    /// `state` is passed to every invocation of `f`, including the ones
+    /// made while shrinking a failure. This makes it possible to, e.g.,
+    /// count how often a branch is hit without resorting to statics.
+    ///
+    /// Since `state` is borrowed, `f` is called on the current thread, so
+    /// a panic in `f` is not turned into a test failure and instead
+    /// propagates to the caller.
+    pub fn run_with_state<S, A, F>(&mut self, state: &mut S, mut f: F)
+                                  -> RunReport
+            where A: Arbitrary + Debug, F: FnMut(&mut S, A) -> TestResult {
+        let max_shrink_time = self.settings.max_shrink_time;
+        self.run_tests(|g| {
+            let mut a: A = arby(g);
+            let mut r = f(state, a.clone());
+            if !r.is_failure() {
+                return r;
+            }
+            r.arguments = vec![format!("{:?}", a)];
+            let deadline = max_shrink_time.map(|t| Instant::now() + t);
+            let mut shrinks = 0;
+            'shrinking: loop {
+                for sa in a.shrink() {
+                    if past(deadline) {
+                        return r;
+                    }
+                    let mut sr = f(state, sa.clone());
+                    if sr.is_failure() {
+                        shrinks += 1;
+                        sr.arguments = vec![format!("{:?}", sa)];
+                        sr.shrinks = shrinks;
+                        r = sr;
+                        a = sa;
+                        continue 'shrinking;
+                    }
+                }
+                return r;
+            }
+        })
+    }
+
+    /// Tests that two implementations of the same function agree on every
+    /// input and returns a report describing the whole run.
+    ///
+    /// When `fast` and `slow` return different outputs, the input is
+    /// shrunk while they still disagree. The error of the failing test
+    /// shows what both of them returned for the shrunk input.
+    ///
+    /// Like `run_with_state`, both functions are called on the current
+    /// thread, so a panic in either of them propagates to the caller.
+    pub fn differential<A, O, F, S>(&mut self, fast: F, slow: S) -> RunReport
+            where A: Arbitrary + Debug, O: PartialEq + Debug,
+                  F: Fn(&A) -> O, S: Fn(&A) -> O {
+        self.run_with_state(&mut (), |_, a: A| {
+            let (x, y) = (fast(&a), slow(&a));
+            if x == y {
+                TestResult::passed()
+            } else {
+                TestResult::error(&format!(
+                    "fast implementation returned {:?}, but slow \
+                     implementation returned {:?}", x, y))
+            }
+        })
+    }
+
+    /// Tests the state machine `sm` on random sequences of commands and
+    /// returns a report describing the whole run.
+    ///
+    /// Each test applies up to `size` commands, where `size` is the size of
+    /// the generator. A command whose precondition doesn't hold is replaced
+    /// with a new one instead of discarding the whole test. A sequence
+    /// after which the invariant fails is shrunk to a smaller sequence that
+    /// still satisfies every precondition, which is reported as the only
+    /// argument of the failure.
+    pub fn state_machine<S: StateMachine>(&mut self, sm: S) -> RunReport {
+        let max_shrink_time = self.settings.max_shrink_time;
+        self.run_tests(|g| {
+            let (cmds, failed) = state_machine::generate(&sm, g);
+            if !failed {
+                return TestResult::passed();
+            }
+            let deadline = max_shrink_time.map(|t| Instant::now() + t);
+            let (cmds, shrinks) = state_machine::shrink(&sm, cmds, deadline);
+            let mut r = TestResult::failed();
+            r.arguments = vec![format!("{:?}", cmds)];
+            r.shrinks = shrinks;
+            r
+        })
+    }
+
+    /// Tests a property without stopping at the first failure, and groups
+    /// the (shrunk) failures by the label `classify` gives their input.
+    ///
+    /// Inputs are classified as they were first generated, before any
+    /// shrinking. This is useful for triaging a property that can fail in
+    /// several different ways.
+    ///
+    /// Seeds set with `regressions_dir` or `always_seeds` are replayed
+    /// first, the same way `run` replays them, and their failures are
+    /// grouped with the rest.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use quickcheck::QuickCheck;
+    ///
+    /// fn prop(x: i32) -> bool { x > -10 && x < 10 }
+    /// let failures = QuickCheck::new().labeled(
+    ///     prop as fn(i32) -> bool,
+    ///     |&x| if x < 0 { "negative" } else { "positive" }.to_string());
+    /// for (label, results) in failures.iter() {
+    ///     println!("{} failures in category {}", results.len(), label);
+    /// }
+    /// ```
+    pub fn labeled<A, T, C>(&mut self, f: fn(A) -> T, classify: C)
+                           -> BTreeMap<String, Vec<TestResult>>
+            where A: Arbitrary + Debug, T: Testable + 'static,
+                  C: Fn(&A) -> String {
+        let mut groups = BTreeMap::new();
+        for seed in self.replay.clone() {
+            let mut g = self.seeded(seed);
+            test_labeled(&mut g, f, &classify, &self.settings, &mut groups);
+        }
         let mut ntests: usize = 0;
         for _ in 0..self.max_tests {
             if ntests >= self.tests {
                 break
             }
-            let r = f.result(&mut self.gen);
-            match r.status {
-                Pass => ntests += 1,
-                Discard => continue,
-                Fail => return Err(r),
+            let seed = self.gen.next_u64();
+            let mut g = self.seeded(seed);
+            let status = test_labeled(&mut g, f, &classify, &self.settings,
+                                      &mut groups);
+            if status != Discard {
+                ntests += 1;
+            }
+        }
+        for (label, results) in groups.iter() {
+            info!("{} QuickCheck failures labeled '{}'.",
+                  results.len(), label);
+        }
+        groups
+    }
+
+    /// Returns the generator that the test with the given seed generates
+    /// its arguments with.
+    fn seeded(&self, seed: u64) -> StdGen<SplitMix64> {
+        let mut g = StdGen::from_seed(seed, self.gen.size());
+        g.set_min_size(self.gen.min_size());
+        g.set_depth(self.gen.depth());
+        g.set_corner_values(self.gen.corner_values());
+        if let Some(fixtures) = self.gen.fixtures() {
+            g.set_fixtures(fixtures.clone());
+        }
+        g
+    }
+
+    /// Runs `test` on every seed to replay and then on seeds drawn from
+    /// the configured generator until enough tests have passed or one
+    /// fails.
+    fn run_tests<F>(&mut self, mut test: F) -> RunReport
+            where F: FnMut(&mut StdGen<SplitMix64>) -> TestResult {
+        let start = Instant::now();
+        let mut report = RunReport {
+            passed: 0,
+            discarded: 0,
+            tests: 0,
+            shrinks: 0,
+            elapsed: Duration::from_secs(0),
+            failure: None,
+            seed: None,
+            sizes: BTreeMap::new(),
+            shrink_path: vec![],
+        };
+        let settings = self.settings;
+        let previous = SETTINGS.with(|s| {
+            let previous = s.get();
+            s.set(settings);
+            previous
+        });
+        for seed in self.replay.clone() {
+            if report.failure.is_some() {
+                break
+            }
+            let r = test(&mut self.seeded(seed));
+            report.record(seed, r);
+        }
+        let replayed = report.passed;
+        for _ in 0..self.max_tests {
+            let done = report.passed - replayed >= self.tests;
+            if done || report.failure.is_some() {
+                break
             }
+            let seed = self.gen.next_u64();
+            let r = test(&mut self.seeded(seed));
+            report.record(seed, r);
         }
-        Ok(ntests)
+        SETTINGS.with(|s| s.set(previous));
+        report.elapsed = start.elapsed();
+        report
     }
 
     /// Tests a property and calls `panic!` on failure.
     ///
     /// The `panic!` message will include a (hopefully) minimal witness of
-    /// failure.
+    /// failure and the seed of the failing test, which can be replayed
+    /// with `always_seeds` or `regressions_dir`.
     ///
     /// It is appropriate to use this method with Rust's unit testing
     /// infrastructure.
     ///
     /// Note that if the environment variable `RUST_LOG` is set to enable
     /// `info` level log messages for the `quickcheck` crate, then this will
-    /// include output on how many QuickCheck tests were passed.
+    /// include output on how many QuickCheck tests were passed and the
+    /// largest size recorded with `TestResult::with_size`. A warning is
+    /// logged if that size is less than half of the generator's size.
     ///
     /// # Example
     ///
@@ -110,13 +486,104 @@ impl<G: Gen> QuickCheck<G> {
     /// }
     /// ```
     pub fn quickcheck<A>(&mut self, f: A) where A: Testable {
-        match self.quicktest(f) {
-            Ok(ntests) => info!("(Passed {} QuickCheck tests.)", ntests),
-            Err(result) => panic!(result.failed_msg()),
+        let report = self.run(f);
+        if let (Some(r), Some(seed)) = (report.failure.as_ref(), report.seed) {
+            panic!("{}\nSeed: {}", r.failed_msg(), seed);
+        }
+        info!("(Passed {} QuickCheck tests.)", report.passed);
+        if let Some(max) = report.max_size() {
+            info!("(Largest size tested: {}.)", max);
+            if max < self.gen.size() / 2 {
+                warn!("The largest size tested was {}, but the generator \
+                       size is {}.", max, self.gen.size());
+            }
+        }
+    }
+}
+
+/// The outcome of running QuickCheck on a property with `QuickCheck::run`.
+#[derive(Clone, Debug)]
+pub struct RunReport {
+    /// The number of tests that passed.
+    pub passed: usize,
+    /// The number of tests that were discarded.
+    pub discarded: usize,
+    /// The number of times the property was tested, not counting the tests
+    /// run while shrinking a failure.
+    pub tests: usize,
+    /// The number of times a failure was successfully shrunk to a smaller
+    /// one.
+    pub shrinks: usize,
+    /// How long the run took, including shrinking.
+    pub elapsed: Duration,
+    /// The (shrunk) failing test, if the property failed.
+    pub failure: Option<TestResult>,
+    /// The seed of the failing test, if the property failed.
+    ///
+    /// Passing it to `QuickCheck::always_seeds` reproduces the failure.
+    pub seed: Option<u64>,
+    /// How many tests were run with each size recorded with
+    /// `TestResult::with_size`. Discarded tests aren't included.
+    pub sizes: BTreeMap<usize, usize>,
+    /// The arguments of every failure that shrinking went through, from
+    /// the original counterexample to `failure`.
+    ///
+    /// This is only recorded with `QuickCheck::trace_shrink_path`.
+    pub shrink_path: Vec<Vec<String>>,
+}
+
+impl RunReport {
+    /// Returns `true` if and only if no test failed.
+    pub fn is_success(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// Returns the largest size recorded with `TestResult::with_size`, if
+    /// any.
+    pub fn max_size(&self) -> Option<usize> {
+        self.sizes.keys().next_back().map(|&n| n)
+    }
+
+    /// Adds the result of the test with the given seed to the report.
+    fn record(&mut self, seed: u64, r: TestResult) {
+        self.tests += 1;
+        if let Some(size) = r.size {
+            if r.status != Discard {
+                *self.sizes.entry(size).or_insert(0) += 1;
+            }
+        }
+        match r.status {
+            Pass => self.passed += 1,
+            Discard => self.discarded += 1,
+            Fail => {
+                self.shrinks = r.shrinks;
+                self.shrink_path = r.shrink_path.clone();
+                self.failure = Some(r);
+                self.seed = Some(seed);
+            }
         }
     }
 }
 
+fn read_seeds(path: &Path) -> Option<Vec<u64>> {
+    let mut contents = String::new();
+    if File::open(path).and_then(|mut f| f.read_to_string(&mut contents))
+                       .is_err() {
+        return None;
+    }
+    let mut seeds = vec![];
+    for line in contents.lines().map(|line| line.trim()) {
+        if line.is_empty() || line.starts_with("#") {
+            continue;
+        }
+        match line.parse() {
+            Ok(seed) => seeds.push(seed),
+            Err(_) => return None,
+        }
+    }
+    if seeds.is_empty() { None } else { Some(seeds) }
+}
+
 /// Convenience function for running QuickCheck.
 ///
 /// This is an alias for `QuickCheck::new().quickcheck(f)`.
@@ -130,12 +597,25 @@ pub struct TestResult {
     status: Status,
     arguments: Vec<String>,
     err: String,
+    shrinks: usize,
+    size: Option<usize>,
+    shrink_path: Vec<Vec<String>>,
 }
 
 /// Whether a test has passed, failed or been discarded.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 enum Status { Pass, Fail, Discard }
 
+impl Status {
+    fn describe(&self) -> &'static str {
+        match *self {
+            Pass => "passed",
+            Fail => "failed",
+            Discard => "was discarded",
+        }
+    }
+}
+
 impl TestResult {
     /// Produces a test result that indicates the current test has passed.
     pub fn passed() -> TestResult { TestResult::from_bool(true) }
@@ -160,6 +640,9 @@ impl TestResult {
             status: Discard,
             arguments: vec![],
             err: "".to_string(),
+            shrinks: 0,
+            size: None,
+            shrink_path: vec![],
         }
     }
 
@@ -171,6 +654,9 @@ impl TestResult {
             status: if b { Pass } else { Fail },
             arguments: vec![],
             err: "".to_string(),
+            shrinks: 0,
+            size: None,
+            shrink_path: vec![],
         }
     }
 
@@ -186,6 +672,22 @@ impl TestResult {
                             .is_err())
     }
 
+    /// Records a measure of how big the input of this test was, such as
+    /// the length of a vector.
+    ///
+    /// The sizes of all tests that weren't discarded are collected in the
+    /// `sizes` of a `RunReport`, which shows whether a property was actually
+    /// tested on large inputs.
+    pub fn with_size(mut self, size: usize) -> TestResult {
+        self.size = Some(size);
+        self
+    }
+
+    /// Returns the size recorded with `with_size`, if any.
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
     /// Returns `true` if and only if this test result describes a failing
     /// test.
     pub fn is_failure(&self) -> bool {
@@ -201,7 +703,17 @@ impl TestResult {
         self.is_failure() && self.err.len() > 0
     }
 
-    fn failed_msg(&self) -> String {
+    /// Returns the `Debug` representation of the arguments that the test
+    /// failed with.
+    ///
+    /// This is empty unless the test failed.
+    pub fn arguments(&self) -> &[String] {
+        &self.arguments
+    }
+
+    /// Returns the message `quickcheck` panics with when this test fails,
+    /// including its arguments and error, if any.
+    pub fn failed_msg(&self) -> String {
         if self.err.len() == 0 {
             format!(
                 "[quickcheck] TEST FAILED. Arguments: ({})",
@@ -289,12 +801,17 @@ impl<A, B, C, D, T,> Testable for fn(A, B, C, D) -> T
     }
 }
 
-trait Fun<A, B, C, D, T> : ::std::marker::PhantomFn<T> {
+trait Fun<A, B, C, D, T> {
     fn call<G>(&self, g: &mut G,
                a: Option<&A>, b: Option<&B>,
                c: Option<&C>, d: Option<&D>)
               -> TestResult
               where G: Gen;
+
+    fn arguments(&self,
+                 a: Option<&A>, b: Option<&B>,
+                 c: Option<&C>, d: Option<&D>)
+                -> Vec<String>;
 }
 
 macro_rules! impl_fun_call {
@@ -312,6 +829,12 @@ macro_rules! impl_fun_call {
     });
 }
 
+macro_rules! impl_fun_arguments {
+    ($($name:ident,)+) => ({
+        vec![$(format!("{:?}", $name.unwrap()),)*]
+    });
+}
+
 impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn() -> T
     where A: AShow, B: AShow, C: AShow, D: AShow, T: Testable + 'static {
     fn call<G>(&self, g: &mut G,
@@ -321,6 +844,13 @@ impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn() -> T
         let f = *self;
         safe(move || { f() }).result(g)
     }
+
+    fn arguments(&self,
+                 _: Option<&A>, _: Option<&B>,
+                 _: Option<&C>, _: Option<&D>)
+                -> Vec<String> {
+        vec![]
+    }
 }
 
 impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn(A) -> T
@@ -331,6 +861,13 @@ impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn(A) -> T
               -> TestResult where G: Gen {
         impl_fun_call!(*self, g, a,)
     }
+
+    fn arguments(&self,
+                 a: Option<&A>, _: Option<&B>,
+                 _: Option<&C>, _: Option<&D>)
+                -> Vec<String> {
+        impl_fun_arguments!(a,)
+    }
 }
 
 impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn(A, B) -> T
@@ -341,6 +878,13 @@ impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn(A, B) -> T
               -> TestResult where G: Gen {
         impl_fun_call!(*self, g, a, b,)
     }
+
+    fn arguments(&self,
+                 a: Option<&A>, b: Option<&B>,
+                 _: Option<&C>, _: Option<&D>)
+                -> Vec<String> {
+        impl_fun_arguments!(a, b,)
+    }
 }
 
 impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn(A, B, C) -> T
@@ -351,6 +895,13 @@ impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn(A, B, C) -> T
               -> TestResult where G: Gen {
         impl_fun_call!(*self, g, a, b, c,)
     }
+
+    fn arguments(&self,
+                 a: Option<&A>, b: Option<&B>,
+                 c: Option<&C>, _: Option<&D>)
+                -> Vec<String> {
+        impl_fun_arguments!(a, b, c,)
+    }
 }
 
 impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn(A, B, C, D) -> T
@@ -361,6 +912,13 @@ impl<A, B, C, D, T> Fun<A, B, C, D, T> for fn(A, B, C, D) -> T
               -> TestResult where G: Gen {
         impl_fun_call!(*self, g, a, b, c, d,)
     }
+
+    fn arguments(&self,
+                 a: Option<&A>, b: Option<&B>,
+                 c: Option<&C>, d: Option<&D>)
+                -> Vec<String> {
+        impl_fun_arguments!(a, b, c, d,)
+    }
 }
 
 fn shrink<G, T, A, B, C, D, F>(g: &mut G, fun: &F) -> TestResult
@@ -368,36 +926,135 @@ fn shrink<G, T, A, B, C, D, F>(g: &mut G, fun: &F) -> TestResult
           F: Fun<A, B, C, D, T> {
     let (a, b, c, d): (A, B, C, D) = arby(g);
     let r = fun.call(g, Some(&a), Some(&b), Some(&c), Some(&d));
+    let settings = SETTINGS.with(|s| s.get());
+    if settings.deterministic {
+        let again = fun.call(g, Some(&a), Some(&b), Some(&c), Some(&d));
+        if again.status != r.status {
+            let mut r = TestResult::error(&format!(
+                "Property is nondeterministic: it {} the first time and {} \
+                 the second time.",
+                r.status.describe(), again.status.describe()));
+            r.arguments =
+                fun.arguments(Some(&a), Some(&b), Some(&c), Some(&d));
+            return r;
+        }
+    }
     match r.status {
         Pass|Discard => r,
-        Fail => shrink_failure(g, (a, b, c, d).shrink(), fun).unwrap_or(r),
+        Fail => {
+            let target = ShrinkTarget::new(&settings, &r);
+            shrink_from(g, &(a, b, c, d), fun, &target, r)
+        }
+    }
+}
+
+/// Decides which failures found while shrinking may replace the original
+/// one.
+struct ShrinkTarget {
+    /// When to give up shrinking.
+    deadline: Option<Instant>,
+    /// The error (e.g., panic message) every failure must have, if any.
+    err: Option<String>,
+    /// Whether to record the failures shrinking steps through.
+    trace: bool,
+}
+
+impl ShrinkTarget {
+    fn new(settings: &Settings, original: &TestResult) -> ShrinkTarget {
+        ShrinkTarget {
+            deadline: settings.max_shrink_time.map(|t| Instant::now() + t),
+            err: if settings.same_panic && original.is_error() {
+                Some(original.err.clone())
+            } else {
+                None
+            },
+            trace: settings.trace_shrink_path,
+        }
+    }
+
+    fn accepts(&self, r: &TestResult) -> bool {
+        r.is_failure() && self.err.as_ref().map_or(true, |err| *err == r.err)
+    }
+}
+
+/// Tests `f` on an input generated by `g` for `QuickCheck::labeled`, and
+/// adds the shrunk failure, if any, to the group `classify` labels it with.
+fn test_labeled<G, A, T, C>(g: &mut G,
+                            f: fn(A) -> T,
+                            classify: &C,
+                            settings: &Settings,
+                            groups: &mut BTreeMap<String, Vec<TestResult>>)
+                           -> Status
+    where G: Gen, A: Arbitrary + Debug, T: Testable + 'static,
+          C: Fn(&A) -> String {
+    let a: A = arby(g);
+    let r = Fun::<A, (), (), (), T>::call(&f, g, Some(&a), None, None, None);
+    let status = r.status.clone();
+    if status == Fail {
+        let label = classify(&a);
+        let args = (a, (), (), ());
+        let target = ShrinkTarget::new(settings, &r);
+        let r = shrink_from(g, &args, &f, &target, r);
+        groups.entry(label).or_insert(vec![]).push(r);
+    }
+    status
+}
+
+/// Shrinks the failure `r` of `fun` on `args` as far as possible.
+fn shrink_from<G, T, A, B, C, D, F>
+              (g: &mut G,
+               args: &(A, B, C, D),
+               fun: &F,
+               target: &ShrinkTarget,
+               r: TestResult)
+              -> TestResult
+    where G: Gen, T: Testable, A: AShow, B: AShow, C: AShow, D: AShow,
+          F: Fun<A, B, C, D, T> {
+    let original = r.arguments.clone();
+    let mut r = shrink_failure(g, args, fun, target).unwrap_or(r);
+    if target.trace {
+        r.shrink_path.insert(0, original);
     }
+    r
 }
 
 fn shrink_failure<G, T, A, B, C, D, F>
                  (g: &mut G,
-                  shrinker: Box<Iterator<Item=(A, B, C, D)>+'static>,
-                  fun: &F)
+                  args: &(A, B, C, D),
+                  fun: &F,
+                  target: &ShrinkTarget)
                  -> Option<TestResult>
     where G: Gen, T: Testable, A: AShow, B: AShow, C: AShow, D: AShow,
           F: Fun<A, B, C, D, T> {
-    for (a, b, c, d) in shrinker {
+    let total = args.shrink_count();
+    for (i, (a, b, c, d)) in args.shrink().enumerate() {
+        if past(target.deadline) {
+            debug!("Shrinking: out of time.");
+            return None;
+        }
+        if let Some(n) = total {
+            debug!("Shrinking: tried {} of ~{} candidates.", i + 1, n);
+        }
         let r = fun.call(g, Some(&a), Some(&b), Some(&c), Some(&d));
-        match r.status {
-            // The shrunk value does not witness a failure, so
+        if !target.accepts(&r) {
+            // The shrunk value does not witness the failure, so
             // throw it away.
-            Pass|Discard => continue,
+            continue;
+        }
 
-            // The shrunk value *does* witness a failure, so keep trying
-            // to shrink it.
-            Fail => {
-                let shrunk = shrink_failure(g, (a, b, c, d).shrink(), fun);
+        // The shrunk value *does* witness the failure, so keep trying
+        // to shrink it.
+        let step = r.arguments.clone();
+        let shrunk = shrink_failure(g, &(a, b, c, d), fun, target);
 
-                // If we couldn't witness a failure on any shrunk value,
-                // then return the failure we already have.
-                return Some(shrunk.unwrap_or(r))
-            },
+        // If we couldn't witness a failure on any shrunk value,
+        // then return the failure we already have.
+        let mut r = shrunk.unwrap_or(r);
+        r.shrinks += 1;
+        if target.trace {
+            r.shrink_path.insert(0, step);
         }
+        return Some(r)
     }
     None
 }
@@ -423,8 +1080,11 @@ mod trap {
         match t.spawn(run).unwrap().join() {
             Ok(()) => Ok(recv_ret.recv().unwrap()),
             Err(any_err) => {
-                match any_err.downcast_ref::<String>() {
-                    Some(ref s) => Err(s.trim().to_owned()),
+                if let Some(s) = any_err.downcast_ref::<String>() {
+                    return Err(s.trim().to_owned());
+                }
+                match any_err.downcast_ref::<&'static str>() {
+                    Some(s) => Err(s.trim().to_owned()),
                     None => Err("UNABLE TO SHOW RESULT OF PANIC.".to_owned()),
                 }
             }
@@ -432,6 +1092,11 @@ mod trap {
     }
 }
 
+/// Returns `true` if `deadline` is set and has passed.
+fn past(deadline: Option<Instant>) -> bool {
+    deadline.map_or(false, |d| Instant::now() >= d)
+}
+
 /// Convenient aliases.
 trait AShow : Arbitrary + Debug {}
 impl<A: Arbitrary + Debug> AShow for A {}
@@ -126,3 +126,384 @@ fn sieve_of_eratosthenes() {
     }
     quickcheck(prop as fn(usize) -> bool);
 }
+
+#[test]
+fn run_report_passed() {
+    fn prop(x: usize) -> TestResult {
+        if x % 2 == 0 {
+            TestResult::discard()
+        } else {
+            TestResult::passed()
+        }
+    }
+    let report = QuickCheck::new().tests(50)
+                                  .run(prop as fn(usize) -> TestResult);
+    assert!(report.is_success());
+    assert_eq!(report.passed, 50);
+    assert!(report.discarded > 0);
+    assert_eq!(report.tests, report.passed + report.discarded);
+    assert_eq!(report.shrinks, 0);
+    assert_eq!(report.seed, None);
+}
+
+#[test]
+fn run_report_failed() {
+    fn prop(xs: Vec<usize>) -> bool { xs.len() < 3 }
+    let report = QuickCheck::new().run(prop as fn(Vec<usize>) -> bool);
+    assert!(!report.is_success());
+    assert_eq!(report.tests, report.passed + report.discarded + 1);
+    assert!(report.shrinks > 0);
+    let failure = report.failure.unwrap();
+    assert!(failure.is_failure());
+    assert_eq!(failure.arguments(), ["[0, 0, 0]"]);
+
+    let seed = report.seed.unwrap();
+    let replayed = QuickCheck::new()
+        .tests(0)
+        .always_seeds(vec![seed])
+        .run(prop as fn(Vec<usize>) -> bool);
+    assert_eq!(replayed.tests, 1);
+    assert_eq!(replayed.seed, Some(seed));
+    assert_eq!(replayed.failure.unwrap().arguments(), ["[0, 0, 0]"]);
+}
+
+#[test]
+fn failure_message_has_seed() {
+    use std::panic;
+
+    fn prop(xs: Vec<usize>) -> bool { xs.len() < 3 }
+    let err = panic::catch_unwind(|| {
+        QuickCheck::new().quickcheck(prop as fn(Vec<usize>) -> bool)
+    }).unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    let seed: u64 = msg.split("Seed: ").nth(1).unwrap().parse().unwrap();
+    let report = QuickCheck::new()
+        .tests(0)
+        .always_seeds(vec![seed])
+        .run(prop as fn(Vec<usize>) -> bool);
+    assert!(!report.is_success(), "{}", msg);
+}
+
+#[test]
+fn fixtures() {
+    use super::Fixed;
+
+    fn prop(base: Fixed<String>, _: u8) -> bool { base.0 == "base" }
+    QuickCheck::new()
+        .fixture("base".to_string())
+        .quickcheck(prop as fn(Fixed<String>, u8) -> bool);
+}
+
+#[test]
+fn run_with_state_counts() {
+    let mut calls = 0;
+    let report = QuickCheck::new().run_with_state(&mut calls, |calls, x: u8| {
+        *calls += 1;
+        TestResult::from_bool(x < 200)
+    });
+    assert!(report.is_success());
+    assert_eq!(calls, report.tests);
+    assert_eq!(calls, 100);
+}
+
+#[test]
+fn run_with_state_shrinks() {
+    let mut largest = 0;
+    let report = QuickCheck::new().run_with_state(&mut largest, |m, x: usize| {
+        *m = ::std::cmp::max(*m, x);
+        TestResult::from_bool(x < 10)
+    });
+    let failure = report.failure.unwrap();
+    assert_eq!(failure.arguments(), ["10"]);
+    assert!(largest >= 10);
+}
+
+#[test]
+fn labeled_failures() {
+    fn prop(x: isize) -> bool { x > -10 && x < 10 }
+    let groups = QuickCheck::new().labeled(
+        prop as fn(isize) -> bool,
+        |&x| if x < 0 { "negative" } else { "positive" }.to_string());
+    let labels: Vec<&str> = groups.keys().map(|k| &**k).collect();
+    assert_eq!(labels, ["negative", "positive"]);
+    for results in groups.values() {
+        assert!(results.len() > 0);
+        assert!(results.iter().all(|r| r.is_failure()));
+    }
+    assert!(groups["positive"].iter().all(|r| r.arguments() == ["10"]));
+}
+
+#[test]
+fn labeled_replays_seeds() {
+    use super::{Arbitrary, StdGen};
+
+    fn prop(_: Vec<u8>) -> bool { false }
+    let groups = QuickCheck::new()
+        .tests(0)
+        .always_seeds(vec![7, 8])
+        .labeled(prop as fn(Vec<u8>) -> bool, |xs| format!("{:?}", xs));
+    let mut expected: Vec<String> = [7, 8].iter().map(|&seed| {
+        let mut g = StdGen::from_seed(seed, 100);
+        let xs: Vec<u8> = Arbitrary::arbitrary(&mut g);
+        format!("{:?}", xs)
+    }).collect();
+    expected.sort();
+    let labels: Vec<String> = groups.keys().cloned().collect();
+    assert_eq!(labels, expected);
+}
+
+#[test]
+fn regressions_dir_replays_every_file() {
+    use rand::{self, Rng};
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    let dir = env::temp_dir().join(format!("quickcheck-regressions-{}",
+                                           rand::thread_rng().gen::<u64>()));
+    fs::create_dir(&dir).unwrap();
+    let files = [
+        ("alice", "# seeds found by alice\n1\n2\n"),
+        ("ci", "2\n\n3\n"),
+        ("empty", ""),
+        ("malformed", "4\nnot a seed\n"),
+    ];
+    for &(name, contents) in files.iter() {
+        File::create(dir.join(name)).unwrap()
+            .write_all(contents.as_bytes()).unwrap();
+    }
+
+    fn prop(_: Vec<usize>) -> bool { true }
+    let report = QuickCheck::new()
+        .tests(0)
+        .regressions_dir(&dir)
+        .run(prop as fn(Vec<usize>) -> bool);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(report.tests, 3);
+    assert_eq!(report.passed, 3);
+}
+
+#[test]
+fn max_shrink_time() {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn prop(xs: Vec<usize>) -> bool {
+        thread::sleep(Duration::from_millis(5));
+        xs.len() < 5
+    }
+    let start = Instant::now();
+    let report = QuickCheck::new()
+        .max_shrink_time(Duration::from_millis(20))
+        .run(prop as fn(Vec<usize>) -> bool);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    let failure = report.failure.unwrap();
+    // The reported counterexample still fails the property.
+    assert!(failure.arguments()[0].split(',').count() >= 5);
+}
+
+#[test]
+fn differential_reports_both_outputs() {
+    fn fast(xs: &Vec<usize>) -> usize {
+        let sum = xs.iter().fold(0, |a, &b| a + b);
+        if xs.contains(&3) { sum + 1 } else { sum }
+    }
+    fn slow(xs: &Vec<usize>) -> usize {
+        let mut sum = 0;
+        for &x in xs.iter() {
+            sum += x;
+        }
+        sum
+    }
+    let report = QuickCheck::new().tests(1000).differential(fast, slow);
+    let failure = report.failure.unwrap();
+    assert_eq!(failure.arguments(), ["[3]"]);
+    assert!(failure.failed_msg().contains(
+        "fast implementation returned 4, but slow implementation returned 3"));
+}
+
+#[test]
+fn check_deterministic() {
+    use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+    static CALLS: AtomicUsize = ATOMIC_USIZE_INIT;
+    fn prop(_: usize) -> TestResult {
+        if CALLS.fetch_add(1, Ordering::SeqCst) % 2 == 0 {
+            TestResult::passed()
+        } else {
+            TestResult::discard()
+        }
+    }
+    let failure = QuickCheck::new()
+        .check_deterministic()
+        .quicktest(prop as fn(usize) -> TestResult)
+        .unwrap_err();
+    assert_eq!(failure.arguments().len(), 1);
+    assert!(failure.failed_msg().contains(
+        "it passed the first time and was discarded the second time"));
+
+    fn pure(x: usize) -> bool { x == x }
+    QuickCheck::new()
+        .check_deterministic()
+        .quickcheck(pure as fn(usize) -> bool);
+}
+
+#[test]
+fn always_seeds() {
+    fn prop(_: Vec<usize>) -> bool { true }
+    let report = QuickCheck::new()
+        .tests(0)
+        .always_seeds(vec![7, 8, 7])
+        .run(prop as fn(Vec<usize>) -> bool);
+    assert_eq!(report.tests, 2);
+
+    let report = QuickCheck::new()
+        .tests(5)
+        .always_seeds(vec![7, 8])
+        .run(prop as fn(Vec<usize>) -> bool);
+    assert_eq!(report.tests, 7);
+}
+
+#[test]
+fn run_report_sizes() {
+    fn prop(xs: Vec<usize>) -> TestResult {
+        if xs.len() % 10 == 0 {
+            TestResult::discard().with_size(xs.len())
+        } else {
+            TestResult::passed().with_size(xs.len())
+        }
+    }
+    let report = QuickCheck::new().run(prop as fn(Vec<usize>) -> TestResult);
+    assert_eq!(report.sizes.values().fold(0, |a, &b| a + b), 100);
+    assert!(report.sizes.keys().all(|&n| n % 10 != 0));
+    let max = report.max_size().unwrap();
+    assert!(max > 50 && max < 100, "max size {}", max);
+
+    fn no_size(_: usize) -> bool { true }
+    let report = QuickCheck::new().run(no_size as fn(usize) -> bool);
+    assert_eq!(report.max_size(), None);
+}
+
+#[test]
+fn shrink_same_panic() {
+    use rand;
+    use super::StdGen;
+
+    // Random inputs are almost always large, but shrinking a large input
+    // usually passes through medium ones.
+    fn prop(x: usize) -> bool {
+        if x >= 1000 {
+            panic!("large");
+        } else if x >= 100 {
+            panic!("medium");
+        }
+        true
+    }
+    let gen = || StdGen::new(rand::thread_rng(), 1 << 30);
+    let failure = QuickCheck::new()
+        .gen(gen())
+        .shrink_same_panic()
+        .quicktest(prop as fn(usize) -> bool)
+        .unwrap_err();
+    assert_eq!(failure.arguments(), ["1000"]);
+    assert!(failure.failed_msg().ends_with("Error: large"));
+
+    let failure = QuickCheck::new()
+        .gen(gen())
+        .quicktest(prop as fn(usize) -> bool)
+        .unwrap_err();
+    assert_eq!(failure.arguments(), ["100"]);
+    assert!(failure.failed_msg().ends_with("Error: medium"));
+}
+
+#[test]
+fn trace_shrink_path() {
+    use rand;
+    use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+    use super::StdGen;
+
+    static ORIGINAL: AtomicUsize = ATOMIC_USIZE_INIT;
+    fn prop(x: usize) -> bool {
+        if x < 100 {
+            return true;
+        }
+        ORIGINAL.compare_and_swap(0, x, Ordering::SeqCst);
+        false
+    }
+    let report = QuickCheck::new()
+        .gen(StdGen::new(rand::thread_rng(), 1 << 30))
+        .trace_shrink_path()
+        .run(prop as fn(usize) -> bool);
+    let path: Vec<usize> = report.shrink_path.iter()
+        .map(|args| args[0].parse().unwrap())
+        .collect();
+    assert_eq!(path[0], ORIGINAL.load(Ordering::SeqCst));
+    assert_eq!(*path.last().unwrap(), 100);
+    assert_eq!(path.len(), report.shrinks + 1);
+    assert!(path.windows(2).all(|w| w[0] > w[1]), "{:?}", path);
+}
+
+#[test]
+fn state_machine() {
+    use super::{
+        Arbitrary, Gen, Shrink, StateMachine, empty_shrinker,
+        single_shrinker,
+    };
+    use state_machine;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Cmd { Inc, Dec, Reset }
+
+    impl Arbitrary for Cmd {
+        fn arbitrary<G: Gen>(g: &mut G) -> Cmd {
+            g.choose(&[Cmd::Inc, Cmd::Dec, Cmd::Reset]).unwrap().clone()
+        }
+    }
+
+    impl Shrink for Cmd {
+        fn shrink(&self) -> Box<Iterator<Item=Cmd>+'static> {
+            match *self {
+                Cmd::Inc => empty_shrinker(),
+                _ => single_shrinker(Cmd::Inc),
+            }
+        }
+    }
+
+    // A counter that can't go below zero, with a bug at three.
+    struct Counter;
+
+    impl StateMachine for Counter {
+        type State = u32;
+        type Command = Cmd;
+
+        fn initial_state(&self) -> u32 { 0 }
+
+        fn precondition(&self, &n: &u32, cmd: &Cmd) -> bool {
+            *cmd != Cmd::Dec || n > 0
+        }
+
+        fn apply(&self, n: &mut u32, cmd: &Cmd) {
+            match *cmd {
+                Cmd::Inc => *n += 1,
+                Cmd::Dec => *n -= 1,
+                Cmd::Reset => *n = 0,
+            }
+        }
+
+        fn invariant(&self, &n: &u32) -> bool { n < 3 }
+    }
+
+    let report = QuickCheck::new().tests(1000).state_machine(Counter);
+    let failure = report.failure.unwrap();
+    // The first failure found is sometimes already minimal, so the number
+    // of shrinks can't be checked here.
+    assert_eq!(failure.arguments(), ["[Inc, Inc, Inc]"]);
+
+    // Removing the first `Inc` alone would leave a `Dec` at zero, which
+    // must never be kept.
+    let cmds = vec![Cmd::Inc, Cmd::Reset, Cmd::Inc, Cmd::Dec,
+                    Cmd::Inc, Cmd::Inc, Cmd::Inc];
+    let (shrunk, shrinks) = state_machine::shrink(&Counter, cmds, None);
+    assert_eq!(shrunk, [Cmd::Inc, Cmd::Inc, Cmd::Inc]);
+    assert!(shrinks > 0);
+}
@@ -0,0 +1,57 @@
+// This file must compile with nothing but the prelude in scope.
+
+extern crate quickcheck;
+extern crate rand;
+
+use quickcheck::prelude::*;
+
+#[derive(Clone, Debug)]
+struct Even(usize);
+
+impl Arbitrary for Even {
+    fn arbitrary<G: Gen>(g: &mut G) -> Even {
+        let n: usize = Arbitrary::arbitrary(g);
+        Even(n * 2)
+    }
+}
+
+impl Shrink for Even {
+    fn shrink(&self) -> Box<Iterator<Item=Even>+'static> {
+        if self.0 == 0 {
+            empty_shrinker()
+        } else {
+            single_shrinker(Even(0))
+        }
+    }
+}
+
+#[test]
+fn prelude_quickcheck() {
+    fn prop(e: Even) -> bool { e.0 % 2 == 0 }
+    quickcheck(prop as fn(Even) -> bool);
+}
+
+#[test]
+fn prelude_quicktest() {
+    fn prop(e: Even) -> TestResult {
+        if e.0 == 0 {
+            return TestResult::discard();
+        }
+        TestResult::from_bool(e.0 % 2 == 0)
+    }
+    let gen = StdGen::new(rand::thread_rng(), 50);
+    let passed = QuickCheck::new()
+        .gen(gen)
+        .quicktest(prop as fn(Even) -> TestResult)
+        .unwrap();
+    assert!(passed > 0);
+}
+
+#[test]
+fn prelude_testable() {
+    fn check<T: Testable>(t: T) -> bool {
+        let mut gen = StdGen::new(rand::thread_rng(), 10);
+        !t.result(&mut gen).is_failure()
+    }
+    assert!(check(true));
+}